@@ -7,7 +7,7 @@ fn main() {
     let listener = TcpListener::bind("localhost:3000").unwrap();
 
     // create a buffer for reading the requests later
-    let mut buf = [0 as u8; 1028];
+    let mut buf = [0_u8; 1028];
 
     loop {
         // wait until a client connects via tcp
@@ -22,10 +22,10 @@ fn main() {
         //
         // NOTE: this read is not guranteed to read the entire request in a real application a
         // reading loop would be more appropriate see example/hello-world-loop.rs
-        connection.read(&mut buf).unwrap();
+        let len = connection.read(&mut buf).unwrap();
 
         // create a reqse::Request from the read bytes
-        let request = Request::from_bytes(&buf).unwrap();
+        let request = Request::from_bytes(&buf[..len]).unwrap();
 
         println!("got request: {:#?}", &request);
 
@@ -44,9 +44,7 @@ fn main() {
 
         // send response to client
         //
-        // NOTE: the write method might not write the entire response in a real application a write
-        // loop would be more appropriate
-        connection.write(response.finish().as_ref()).unwrap();
+        connection.write_all(response.finish().as_ref()).unwrap();
 
         println!("send response");
     }
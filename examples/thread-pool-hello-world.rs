@@ -0,0 +1,57 @@
+use reqse::{Handler, Request, ResponseBuilder, Router, Server, chain};
+use std::{io, time::Instant};
+
+fn main() -> io::Result<()> {
+    let mut router = Router::new();
+    router
+        .get("/", routes::root)
+        .get("/health_check", routes::health_check)
+        .get("/hello/:name", routes::hello);
+
+    // create a server with 8 worker threads, up to 128 accepted connections wait for a free worker
+    // before the server starts answering with 503 Service Unavailable
+    // every request passes the log middleware before it reaches the router
+    let mut server = Server::bind("localhost:3000", chain(log, router))?;
+    *server.workers_mut() = 8;
+    *server.queue_len_mut() = 128;
+
+    // the handle can be moved to another thread (eg: a signal handler) to stop the server, run
+    // returns once all in-flight requests are answered
+    let _shutdown = server.shutdown_handle()?;
+
+    server.run()
+}
+
+fn log(request: Request, next: &dyn Handler) -> io::Result<ResponseBuilder> {
+    let start = Instant::now();
+    let line = format!("{} {}", request.method(), request.uri());
+
+    let response = next.call(request)?;
+
+    println!("{} -> {} in {:?}", line, response.status(), start.elapsed());
+    Ok(response)
+}
+
+mod routes {
+    use super::*;
+
+    pub fn root(_: Request) -> io::Result<ResponseBuilder> {
+        let mut response = ResponseBuilder::ok();
+        response.body_mut().extend_from_slice(b"Hello World");
+        Ok(response)
+    }
+
+    pub fn health_check(_: Request) -> io::Result<ResponseBuilder> {
+        Ok(ResponseBuilder::ok())
+    }
+
+    pub fn hello(request: Request) -> io::Result<ResponseBuilder> {
+        let name = request.param("name").unwrap_or("World");
+
+        let mut response = ResponseBuilder::ok();
+        response
+            .body_mut()
+            .extend_from_slice(format!("Hello {}", name).as_bytes());
+        Ok(response)
+    }
+}
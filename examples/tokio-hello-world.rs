@@ -1,9 +1,7 @@
+use bytes::BytesMut;
 use reqse::{Method, Request, ResponseBuilder};
 use std::io;
-use tokio::{
-    io::{AsyncReadExt, AsyncWriteExt},
-    net::{TcpListener, TcpStream},
-};
+use tokio::net::{TcpListener, TcpStream};
 
 #[tokio::main]
 async fn main() -> io::Result<()> {
@@ -21,47 +19,31 @@ async fn main() -> io::Result<()> {
 }
 
 async fn handle_connection(mut connection: TcpStream) -> io::Result<()> {
-    let mut buf = [0 as u8; 1028];
+    // keeps the bytes of the next request read together with the current one
+    let mut buf = BytesMut::new();
 
     println!("start handling connection");
 
-    'conn: loop {
-        let mut buf_offset = 0;
-
-        let request = 'read: loop {
-            println!("reading request");
-            if buf_offset >= buf.len() {
-                connection
-                    .write_all(ResponseBuilder::bad_request().finish().as_ref())
-                    .await?;
-                continue 'conn;
-            }
-
-            buf_offset += match connection.read(&mut buf[buf_offset..]).await? {
-                0 => break 'conn,
-                n => n,
-            };
-
-            match Request::from_bytes(&buf) {
-                Ok(req) => break 'read req,
-                Err(reqse::Error::NotEnoughData) => (),
-                Err(err) => {
-                    eprintln!("ERROR while parsing request: {}", err);
-                    connection
-                        .write_all(ResponseBuilder::bad_request().finish().as_ref())
-                        .await?;
-                    continue 'conn;
-                }
+    loop {
+        let request = match reqse::tokio::read_request(&mut connection, &mut buf).await {
+            Ok(Some(request)) => request,
+            Ok(None) => break,
+            Err(err) if err.kind() == io::ErrorKind::InvalidData => {
+                eprintln!("ERROR while parsing request: {}", err);
+                let response = ResponseBuilder::bad_request();
+                reqse::tokio::write_response(&mut connection, response).await?;
+                break;
             }
+            Err(err) => return Err(err),
         };
 
         println!("got request: {:#?}", &request);
 
-        let response = router(request).unwrap_or_else(|_| ResponseBuilder::internal_server_error());
+        let response = router(request.as_request())
+            .unwrap_or_else(|_| ResponseBuilder::internal_server_error());
 
         println!("created response: {:#?}", &response);
-        connection.write_all(response.finish().as_ref()).await?;
-        connection.flush().await?;
+        reqse::tokio::write_response(&mut connection, response).await?;
     }
 
     println!("client closed connection");
@@ -0,0 +1,290 @@
+use std::collections::HashMap;
+
+use crate::{Error, HeaderMap, syntax, vectored};
+
+/// fields which must not be sent as trailers because they control framing, routing,
+/// authentication or the processing of the content, see RFC 9110 section 6.5.1
+const FORBIDDEN_TRAILERS: &[&str] = &[
+    "Age",
+    "Authorization",
+    "Cache-Control",
+    "Connection",
+    "Content-Encoding",
+    "Content-Length",
+    "Content-Range",
+    "Content-Type",
+    "Cookie",
+    "Date",
+    "Expect",
+    "Expires",
+    "Host",
+    "Keep-Alive",
+    "Location",
+    "Max-Forwards",
+    "Pragma",
+    "Proxy-Authenticate",
+    "Proxy-Authorization",
+    "Proxy-Connection",
+    "Range",
+    "Retry-After",
+    "Set-Cookie",
+    "TE",
+    "Trailer",
+    "Transfer-Encoding",
+    "Upgrade",
+    "Vary",
+    "WWW-Authenticate",
+];
+
+/// whether `name` must not be sent as trailer field
+pub(crate) fn is_forbidden_trailer(name: &str) -> bool {
+    FORBIDDEN_TRAILERS
+        .iter()
+        .any(|forbidden| forbidden.eq_ignore_ascii_case(name))
+}
+
+/// checks the trailers of a builder before they are serialized
+pub(crate) fn validate_trailers(trailers: &HashMap<String, String>) -> Result<(), Error> {
+    if trailers.keys().any(|name| is_forbidden_trailer(name)) {
+        return Err(Error::InvalidHeader);
+    }
+
+    syntax::validate_header(trailers)
+}
+
+/// the value of the `Trailer` header announcing `trailers`
+pub(crate) fn trailer_header(trailers: &HashMap<String, String>) -> String {
+    let mut names = trailers.keys().map(String::as_str).collect::<Vec<_>>();
+    names.sort_unstable();
+    names.join(", ")
+}
+
+/// the bytes written before and after a body of `len` bytes to send it as a single chunk
+/// followed by the last chunk and `trailers`
+pub(crate) fn frame(len: u64, trailers: &HashMap<String, String>) -> (Vec<u8>, Vec<u8>) {
+    let mut prefix = Vec::new();
+    let mut suffix = Vec::new();
+
+    if len > 0 {
+        prefix = format!("{:x}\r\n", len).into_bytes();
+        suffix.extend_from_slice(b"\r\n");
+    }
+
+    suffix.push(b'0');
+    for (key, value) in trailers {
+        vectored::push_header_line(&mut suffix, key, value);
+    }
+    suffix.extend_from_slice(b"\r\n\r\n");
+
+    (prefix, suffix)
+}
+
+/// decodes a body sent with `Transfer-Encoding: chunked` from the start of `buf`
+///
+/// returns the decoded body, the trailer fields and the number of bytes the chunked body
+/// occupies in `buf` (including the final chunk and trailer section). The trailer fields are
+/// valid for `HeaderMap::new`, forbidden ones (see `is_forbidden_trailer`) are dropped, as
+/// RFC 9110 section 6.5.1 asks recipients to.
+///
+/// # Error
+/// - `Error::NotEnoughData` if `buf` does not contain the full chunked body
+/// - `Error::InvalidHeader` if the chunk framing or a trailer is malformed
+pub(crate) fn decode(buf: &[u8]) -> Result<(Vec<u8>, String, usize), Error> {
+    let mut body = Vec::new();
+    let mut offset = 0;
+
+    loop {
+        let (line, line_len) = read_line(&buf[offset..])?;
+        offset += line_len;
+
+        let size = parse_size(line)?;
+
+        if size == 0 {
+            break;
+        }
+
+        // the chunk data is followed by CRLF
+        let end = offset.checked_add(size).ok_or(Error::InvalidHeader)?;
+        let next = end.checked_add(2).ok_or(Error::InvalidHeader)?;
+
+        if buf.len() < next {
+            return Err(Error::NotEnoughData);
+        }
+
+        if &buf[end..next] != b"\r\n" {
+            return Err(Error::InvalidHeader);
+        }
+
+        body.extend_from_slice(&buf[offset..end]);
+        offset = next;
+    }
+
+    // trailer section, terminated by an empty line
+    let trailers_start = offset;
+
+    loop {
+        let (line, line_len) = read_line(&buf[offset..])?;
+        offset += line_len;
+
+        if line.is_empty() {
+            break;
+        }
+    }
+
+    let trailers = std::str::from_utf8(&buf[trailers_start..offset - 2])
+        .ok()
+        .ok_or(Error::InvalidUtf8)?;
+    let trailers = HeaderMap::new(trailers)?
+        .iter()
+        .filter(|(name, _)| !is_forbidden_trailer(name))
+        .map(|(name, value)| format!("{name}: {value}"))
+        .collect::<Vec<_>>()
+        .join("\r\n");
+
+    Ok((body, trailers, offset))
+}
+
+/// moves `offset` past the complete chunks of the chunked body in `buf`, so a body arriving in
+/// pieces is checked once instead of being decoded again after every read
+///
+/// returns whether the last chunk and the trailer section are complete, `decode` then succeeds
+/// unless a trailer is malformed
+///
+/// # Error
+/// - `Error::InvalidHeader` if the chunk framing is malformed
+#[cfg(feature = "tokio")]
+pub(crate) fn skip_chunks(buf: &[u8], offset: &mut usize) -> Result<bool, Error> {
+    loop {
+        let (line, line_len) = match read_line(&buf[*offset..]) {
+            Err(Error::NotEnoughData) => return Ok(false),
+            result => result?,
+        };
+
+        let size = parse_size(line)?;
+
+        if size == 0 {
+            let mut end = *offset + line_len;
+
+            loop {
+                match read_line(&buf[end..]) {
+                    Ok((b"", _)) => return Ok(true),
+                    Ok((_, line_len)) => end += line_len,
+                    Err(Error::NotEnoughData) => return Ok(false),
+                    Err(err) => return Err(err),
+                }
+            }
+        }
+
+        let end = (*offset + line_len)
+            .checked_add(size)
+            .ok_or(Error::InvalidHeader)?;
+        let next = end.checked_add(2).ok_or(Error::InvalidHeader)?;
+
+        if buf.len() < next {
+            return Ok(false);
+        }
+
+        if &buf[end..next] != b"\r\n" {
+            return Err(Error::InvalidHeader);
+        }
+
+        *offset = next;
+    }
+}
+
+/// parses the chunk-size `line` without its CRLF
+pub(crate) fn parse_size(line: &[u8]) -> Result<usize, Error> {
+    // chunk extensions are allowed after the size but carry no meaning for us
+    let size = line.split(|b| *b == b';').next().unwrap_or(line);
+    let size = std::str::from_utf8(size).ok().ok_or(Error::InvalidHeader)?;
+    let size = size.trim_end_matches([' ', '\t']);
+
+    if size.is_empty() || !size.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(Error::InvalidHeader);
+    }
+
+    usize::from_str_radix(size, 16)
+        .ok()
+        .ok_or(Error::InvalidHeader)
+}
+
+/// returns the line at the start of `buf` without its CRLF and the length including the CRLF
+pub(crate) fn read_line(buf: &[u8]) -> Result<(&[u8], usize), Error> {
+    let end = buf
+        .windows(2)
+        .position(|w| w == b"\r\n")
+        .ok_or(Error::NotEnoughData)?;
+
+    Ok((&buf[..end], end + 2))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        let buf = b"5\r\nHello\r\n6;ext=1\r\n World\r\n0\r\nExpires: never\r\n\r\nrest";
+        let (body, trailers, len) = decode(buf).unwrap();
+
+        assert_eq!(body, b"Hello World");
+        assert_eq!(trailers, "");
+        assert_eq!(&buf[len..], b"rest");
+    }
+
+    #[test]
+    fn test_decode_incomplete() {
+        assert_eq!(decode(b"5\r\nHel"), Err(Error::NotEnoughData));
+        assert_eq!(decode(b"5\r\nHello\r\n0\r\n"), Err(Error::NotEnoughData));
+        assert_eq!(decode(b"x\r\n"), Err(Error::InvalidHeader));
+        assert_eq!(decode(b"2\r\nabc\r\n"), Err(Error::InvalidHeader));
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn test_skip_chunks() {
+        let buf = b"5\r\nHello\r\n6;ext=1\r\n World\r\n0\r\nExpires: never\r\n\r\n";
+
+        let mut offset = 0;
+        assert_eq!(skip_chunks(&buf[..12], &mut offset), Ok(false));
+        assert_eq!(offset, 10);
+        assert_eq!(skip_chunks(&buf[..40], &mut offset), Ok(false));
+        assert_eq!(offset, 27);
+        assert_eq!(skip_chunks(buf, &mut offset), Ok(true));
+
+        let mut offset = 0;
+        assert_eq!(
+            skip_chunks(b"2\r\nabc\r\n", &mut offset),
+            Err(Error::InvalidHeader)
+        );
+    }
+
+    #[test]
+    fn test_trailers() {
+        // forbidden trailers are dropped on receipt
+        let (_, decoded, _) = decode(b"0\r\nContent-Length: 5\r\nDigest: x\r\n\r\n").unwrap();
+        assert_eq!(decoded, "Digest: x");
+        assert!(decode(b"0\r\n\r\n").unwrap().1.is_empty());
+
+        let mut trailers = HashMap::new();
+        trailers.insert("Grpc-Status".to_owned(), "0".to_owned());
+
+        let (prefix, suffix) = frame(11, &trailers);
+        let mut encoded = prefix;
+        encoded.extend_from_slice(b"Hello World");
+        encoded.extend_from_slice(&suffix);
+        assert_eq!(encoded, b"b\r\nHello World\r\n0\r\nGrpc-Status: 0\r\n\r\n");
+
+        let (body, decoded, len) = decode(&encoded).unwrap();
+        assert_eq!(body, b"Hello World");
+        assert_eq!(
+            HeaderMap::new(&decoded).unwrap().get("grpc-status"),
+            Some("0")
+        );
+        assert_eq!(len, encoded.len());
+
+        assert_eq!(validate_trailers(&trailers), Ok(()));
+        trailers.insert("set-cookie".to_owned(), "a=b".to_owned());
+        assert_eq!(validate_trailers(&trailers), Err(Error::InvalidHeader));
+    }
+}
@@ -0,0 +1,704 @@
+//! a small blocking HTTP/1.1 client
+//!
+//! # Examples
+//! ```no_run
+//! use reqse::{RequestBuilder, Status};
+//!
+//! let response = reqse::client::send("localhost:3000", RequestBuilder::get("/".into())).unwrap();
+//! assert_eq!(response.status(), Status::Ok);
+//! ```
+
+mod body;
+mod cookie_jar;
+mod pool;
+mod redirect;
+
+pub use body::BodyReader;
+pub use cookie_jar::CookieJar;
+pub use pool::ClientPool;
+
+use std::{
+    io::{self, Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    time::Duration,
+};
+
+use crate::{
+    Error, Method, OwnedResponse, RequestBuilder, Response, Status, Upgraded,
+    framing::{self, BodyLength},
+    sse::{self, EventReader},
+    websocket::{self, WebSocket},
+};
+
+/// sends `request` to `addr` with the default `Client` settings, see `Client::send`
+pub fn send(addr: &str, request: RequestBuilder) -> io::Result<OwnedResponse> {
+    Client::new().send(addr, request)
+}
+
+/// settings for sending requests, every request is sent over a new connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Client {
+    connect_timeout: Option<Duration>,
+    read_timeout: Option<Duration>,
+    write_timeout: Option<Duration>,
+    max_response_len: usize,
+    max_redirects: usize,
+}
+
+impl Client {
+    pub fn new() -> Self {
+        Self {
+            connect_timeout: Some(Duration::from_secs(10)),
+            read_timeout: Some(Duration::from_secs(30)),
+            write_timeout: Some(Duration::from_secs(30)),
+            max_response_len: 16 * 1024 * 1024,
+            max_redirects: 0,
+        }
+    }
+
+    /// how long to wait for the connection to be established, `None` waits forever
+    pub fn connect_timeout(&self) -> Option<Duration> {
+        self.connect_timeout
+    }
+
+    pub fn connect_timeout_mut(&mut self) -> &mut Option<Duration> {
+        &mut self.connect_timeout
+    }
+
+    /// how long a single read from the connection may block, `None` waits forever
+    pub fn read_timeout(&self) -> Option<Duration> {
+        self.read_timeout
+    }
+
+    pub fn read_timeout_mut(&mut self) -> &mut Option<Duration> {
+        &mut self.read_timeout
+    }
+
+    /// how long a single write to the connection may block, `None` waits forever
+    pub fn write_timeout(&self) -> Option<Duration> {
+        self.write_timeout
+    }
+
+    pub fn write_timeout_mut(&mut self) -> &mut Option<Duration> {
+        &mut self.write_timeout
+    }
+
+    /// maximum number of bytes a response (header and body) may occupy
+    pub fn max_response_len(&self) -> usize {
+        self.max_response_len
+    }
+
+    pub fn max_response_len_mut(&mut self) -> &mut usize {
+        &mut self.max_response_len
+    }
+
+    /// maximum number of redirects (`301`, `302`, `303`, `307` and `308`) followed for a single
+    /// request, with `0` (the default) redirect responses are returned as they are
+    pub fn max_redirects(&self) -> usize {
+        self.max_redirects
+    }
+
+    pub fn max_redirects_mut(&mut self) -> &mut usize {
+        &mut self.max_redirects
+    }
+
+    /// sends `request` to `addr` (eg: `localhost:3000`) and reads the response
+    ///
+    /// the `Host` header is set to `addr` unless the request already has one. The connection is
+    /// closed after the response was read.
+    ///
+    /// redirects are followed up to `max_redirects` times. `303 See Other` turns the request into
+    /// a `GET` without body, `301` and `302` do so only for `POST`, `307` and `308` resend the
+    /// request unchanged. `Host`, `Authorization` and `Cookie` are dropped when a redirect leads
+    /// to another host.
+    ///
+    /// # Error
+    /// - `io::ErrorKind::TimedOut` or `io::ErrorKind::WouldBlock` if one of the timeouts expired
+    /// - `io::ErrorKind::UnexpectedEof` if the server closed the connection before the response
+    ///   was complete
+    /// - `io::ErrorKind::InvalidData` if the response is malformed or exceeds `max_response_len`
+    ///   or a redirect has an invalid `Location`
+    /// - `io::ErrorKind::Unsupported` if a redirect leads to a scheme other than `http`
+    /// - `io::ErrorKind::Other` if more than `max_redirects` redirects were received
+    pub fn send(&self, addr: &str, request: RequestBuilder) -> io::Result<OwnedResponse> {
+        self.send_with(addr, request, None)
+    }
+
+    /// like `Client::send` but adds matching cookies from `jar` to every request (redirects
+    /// included) and stores the cookies set by every response in `jar`
+    pub fn send_with_cookies(
+        &self,
+        addr: &str,
+        request: RequestBuilder,
+        jar: &mut CookieJar,
+    ) -> io::Result<OwnedResponse> {
+        self.send_with(addr, request, Some(jar))
+    }
+
+    fn send_with(
+        &self,
+        addr: &str,
+        mut request: RequestBuilder,
+        mut jar: Option<&mut CookieJar>,
+    ) -> io::Result<OwnedResponse> {
+        let mut addr = addr.to_owned();
+        let mut redirects = 0;
+
+        loop {
+            let mut hop = request.clone();
+            if let Some(jar) = jar.as_deref_mut() {
+                jar.apply(&addr, &mut hop);
+            }
+
+            let response = self.send_once(&addr, hop)?;
+
+            if let Some(jar) = jar.as_deref_mut() {
+                jar.store(&addr, request.uri(), &response.header());
+            }
+
+            let Some(location) = redirect::location(&response) else {
+                return Ok(response);
+            };
+
+            if self.max_redirects == 0 {
+                return Ok(response);
+            }
+
+            if redirects == self.max_redirects {
+                return Err(io::Error::other("too many redirects"));
+            }
+            redirects += 1;
+
+            let (next_addr, next_uri) = redirect::resolve(&addr, request.uri(), location)?;
+
+            redirect::rewrite(&mut request, response.status());
+
+            if !next_addr.eq_ignore_ascii_case(&addr) {
+                request.header_mut().retain(|key, _| {
+                    !["Host", "Authorization", "Cookie"]
+                        .iter()
+                        .any(|k| k.eq_ignore_ascii_case(key))
+                });
+            }
+
+            *request.uri_mut() = next_uri;
+            addr = next_addr;
+        }
+    }
+
+    fn send_once(&self, addr: &str, mut request: RequestBuilder) -> io::Result<OwnedResponse> {
+        let mut stream = self.connect(addr)?;
+        let method = request.method();
+
+        set_default_header(&mut request, "Host", addr);
+        set_default_header(&mut request, "Connection", "close");
+
+        request.write_to(&mut stream)?;
+        stream.flush()?;
+
+        read_response(&mut stream, &mut Vec::new(), self.max_response_len, method)
+    }
+
+    /// opens a WebSocket connection to `addr` (eg: `localhost:3000`) with the opening handshake
+    /// `request`, see `RequestBuilder::websocket`
+    ///
+    /// the `Host` header is set to `addr` unless the request already has one. The handshake uses
+    /// the timeouts of the client, afterwards reads block until a message arrives, since idle
+    /// WebSocket connections are normal. Redirects are not followed. With the `deflate` feature
+    /// enabled, permessage-deflate is used if the request offered it (see `Deflate::offer`) and
+    /// the server accepted it.
+    ///
+    /// # Error
+    /// see `Client::send`, additionally
+    /// - `io::ErrorKind::InvalidData` if the server rejected the handshake, see
+    ///   `websocket::validate_response`
+    pub fn websocket(
+        &self,
+        addr: &str,
+        mut request: RequestBuilder,
+    ) -> io::Result<(WebSocket<Upgraded>, OwnedResponse)> {
+        let mut stream = self.connect(addr)?;
+
+        set_default_header(&mut request, "Host", addr);
+
+        request.write_to(&mut stream)?;
+        stream.flush()?;
+
+        // frames the server sent right behind the response stay in `buf`
+        let mut buf = Vec::new();
+        let response = read_response(&mut stream, &mut buf, self.max_response_len, Method::Get)?;
+
+        websocket::validate_response(&request, &response.as_response()).map_err(|_| {
+            io::Error::new(io::ErrorKind::InvalidData, "websocket handshake rejected")
+        })?;
+
+        stream.set_read_timeout(None)?;
+
+        #[cfg_attr(not(feature = "deflate"), allow(unused_mut))]
+        let mut socket = WebSocket::client(Upgraded::new(stream, buf));
+
+        #[cfg(feature = "deflate")]
+        socket.set_deflate(websocket::negotiate_deflate(
+            &request,
+            &response.as_response(),
+        )?);
+
+        Ok((socket, response))
+    }
+
+    /// requests the event stream `request` from `addr` (eg: `localhost:3000`) and returns a
+    /// reader for its events, see `RequestBuilder::event_stream`
+    ///
+    /// the `Host` header is set to `addr` unless the request already has one. The returned
+    /// response holds the head, its body is read by the `EventReader`. A `Last-Event-ID` in
+    /// `request` becomes the initial `EventReader::last_event_id`, so a reader created for a
+    /// reconnect keeps the id even if the server sends none. Reads block until an event
+    /// arrives. Redirects are not followed.
+    ///
+    /// # Error
+    /// see `Client::send`, additionally
+    /// - `io::ErrorKind::InvalidData` if the response is not a `200 OK` with
+    ///   `Content-Type: text/event-stream`, a client should not reconnect then
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use reqse::{RequestBuilder, client::Client};
+    ///
+    /// let client = Client::new();
+    /// let request = RequestBuilder::event_stream("/events".into());
+    /// let (mut events, _) = client.event_stream("localhost:3000", request).unwrap();
+    ///
+    /// while let Some(event) = events.read().unwrap() {
+    ///     println!("{}: {}", event.event().unwrap_or("message"), event.data());
+    /// }
+    /// ```
+    pub fn event_stream(
+        &self,
+        addr: &str,
+        mut request: RequestBuilder,
+    ) -> io::Result<(EventReader<BodyReader<TcpStream>>, OwnedResponse)> {
+        let mut stream = self.connect(addr)?;
+
+        set_default_header(&mut request, "Host", addr);
+
+        request.write_to(&mut stream)?;
+        stream.flush()?;
+
+        // the start of the body may have arrived together with the head
+        let mut buf = Vec::new();
+        let (response, length) =
+            read_head(&mut stream, &mut buf, self.max_response_len, Method::Get)?;
+
+        let event_stream = response
+            .header()
+            .get("Content-Type")
+            .is_some_and(sse::is_content_type);
+        if response.status() != Status::Ok || !event_stream {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "response is not an event stream",
+            ));
+        }
+
+        stream.set_read_timeout(None)?;
+
+        let mut events = EventReader::new(BodyReader::new(stream, buf, length));
+        if let Some((_, id)) = request
+            .header()
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case("Last-Event-ID"))
+        {
+            id.clone_into(events.last_event_id_mut());
+        }
+
+        Ok((events, response))
+    }
+
+    pub(crate) fn connect(&self, addr: &str) -> io::Result<TcpStream> {
+        let stream = match self.connect_timeout {
+            None => TcpStream::connect(addr)?,
+            Some(timeout) => {
+                let mut last_err = None;
+                let mut stream = None;
+
+                for addr in addr.to_socket_addrs()? {
+                    match TcpStream::connect_timeout(&addr, timeout) {
+                        Ok(s) => {
+                            stream = Some(s);
+                            break;
+                        }
+                        Err(err) => last_err = Some(err),
+                    }
+                }
+
+                match (stream, last_err) {
+                    (Some(stream), _) => stream,
+                    (None, Some(err)) => return Err(err),
+                    (None, None) => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            "could not resolve address",
+                        ));
+                    }
+                }
+            }
+        };
+
+        stream.set_read_timeout(self.read_timeout)?;
+        stream.set_write_timeout(self.write_timeout)?;
+        stream.set_nodelay(true)?;
+
+        Ok(stream)
+    }
+}
+
+impl Default for Client {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// inserts `key: value` into the header of `request` unless a field named `key` (in any casing)
+/// is already present
+pub(crate) fn set_default_header(request: &mut RequestBuilder, key: &str, value: &str) {
+    let present = request.header().keys().any(|k| k.eq_ignore_ascii_case(key));
+
+    if !present {
+        request
+            .header_mut()
+            .insert(key.to_owned(), value.to_owned());
+    }
+}
+
+/// reads the response to a request with `method` from `reader`
+///
+/// `buf` may already contain bytes read earlier from the same connection, the bytes belonging
+/// to the response are removed from it, anything after the response stays in `buf`
+pub(crate) fn read_response<R: Read>(
+    reader: &mut R,
+    buf: &mut Vec<u8>,
+    max_len: usize,
+    method: Method,
+) -> io::Result<OwnedResponse> {
+    let (head, length) = read_head(reader, buf, max_len, method)?;
+    let max_body_len = max_len.saturating_sub(head.header().as_str().len());
+
+    // the head is parsed once, the body is decoded while it arrives
+    let mut body_reader = BodyReader::new(reader, std::mem::take(buf), length);
+    let mut body = Vec::new();
+    let mut chunk = [0_u8; 8192];
+
+    loop {
+        match body_reader.read(&mut chunk) {
+            Ok(0) => break,
+            Ok(n) => body.extend_from_slice(&chunk[..n]),
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => (),
+            Err(err) => return Err(err),
+        }
+
+        if body.len() > max_body_len {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "response exceeds maximum length",
+            ));
+        }
+    }
+
+    let (_, rest, trailers) = body_reader.into_parts();
+    *buf = rest;
+
+    Ok(OwnedResponse::new(
+        head.version(),
+        head.status(),
+        head.header().as_str().to_owned(),
+        body,
+        trailers,
+    ))
+}
+
+/// reads the head of the response to a request with `method` from `reader`, interim responses
+/// are skipped
+///
+/// returns the response without body and how the body is framed, the bytes behind the head
+/// stay in `buf`
+fn read_head<R: Read>(
+    reader: &mut R,
+    buf: &mut Vec<u8>,
+    max_len: usize,
+    method: Method,
+) -> io::Result<(OwnedResponse, BodyLength)> {
+    let mut chunk = [0_u8; 8192];
+    // the head may only end behind the bytes already searched
+    let mut searched: usize = 0;
+
+    loop {
+        let complete = buf[searched.saturating_sub(3)..]
+            .windows(4)
+            .any(|window| window == b"\r\n\r\n");
+
+        if complete {
+            match Response::parse_head(buf) {
+                Ok((_, status, _, len)) if status.is_interim() => {
+                    buf.drain(..len);
+                    searched = 0;
+                    continue;
+                }
+                Ok((version, status, header, len)) => {
+                    let length = framing::response_body_length(method, status, &header)?;
+                    let header = header.as_str().to_owned();
+                    buf.drain(..len);
+
+                    let response =
+                        OwnedResponse::new(version, status, header, Vec::new(), String::new());
+                    return Ok((response, length));
+                }
+                Err(Error::NotEnoughData) => (),
+                Err(err) => return Err(err.into()),
+            }
+        }
+        searched = buf.len();
+
+        if buf.len() > max_len {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "response exceeds maximum length",
+            ));
+        }
+
+        match reader.read(&mut chunk) {
+            Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(n) => buf.extend_from_slice(&chunk[..n]),
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => (),
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Request, Status};
+    use std::{net::TcpListener, thread};
+
+    /// serves a single connection with `response` and returns the raw request that was received
+    fn serve_once(response: &'static [u8]) -> (String, thread::JoinHandle<Vec<u8>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+
+        let handle = thread::spawn(move || {
+            let (mut connection, _) = listener.accept().unwrap();
+            let mut buf = Vec::new();
+            let mut chunk = [0_u8; 1024];
+
+            while Request::from_bytes(&buf).is_err() {
+                let n = connection.read(&mut chunk).unwrap();
+                buf.extend_from_slice(&chunk[..n]);
+            }
+
+            connection.write_all(response).unwrap();
+            buf
+        });
+
+        (addr, handle)
+    }
+
+    #[test]
+    fn test_send_content_length() {
+        let (addr, server) = serve_once(b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nHello");
+
+        let mut request = RequestBuilder::post("/echo".into());
+        request.body_but().extend_from_slice(b"ping");
+        let response = send(&addr, request).unwrap();
+
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.body(), b"Hello");
+
+        let raw_request = server.join().unwrap();
+        let request = Request::from_bytes(&raw_request).unwrap();
+        assert_eq!(request.header().get("Host"), Some(addr.as_str()));
+        assert_eq!(request.body(), b"ping");
+    }
+
+    #[test]
+    fn test_send_chunked() {
+        let (addr, server) = serve_once(
+            b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nHello\r\n6\r\n World\r\n0\r\n\r\n",
+        );
+
+        let response = send(&addr, RequestBuilder::get("/".into())).unwrap();
+        assert_eq!(response.body(), b"Hello World");
+
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_send_until_eof() {
+        let (addr, server) = serve_once(b"HTTP/1.0 200 OK\r\n\r\nHello World");
+
+        let response = send(&addr, RequestBuilder::get("/".into())).unwrap();
+        assert_eq!(response.body(), b"Hello World");
+
+        server.join().unwrap();
+    }
+
+    /// serves one connection per entry of `responses` and returns the raw requests
+    fn serve_all(responses: Vec<&'static [u8]>) -> (String, thread::JoinHandle<Vec<Vec<u8>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+
+        let handle = thread::spawn(move || {
+            let mut requests = Vec::new();
+
+            for response in responses {
+                let (mut connection, _) = listener.accept().unwrap();
+                let mut buf = Vec::new();
+                let mut chunk = [0_u8; 1024];
+
+                while Request::from_bytes(&buf).is_err() {
+                    let n = connection.read(&mut chunk).unwrap();
+                    buf.extend_from_slice(&chunk[..n]);
+                }
+
+                connection.write_all(response).unwrap();
+                requests.push(buf);
+            }
+
+            requests
+        });
+
+        (addr, handle)
+    }
+
+    #[test]
+    fn test_follow_redirects_with_cookies() {
+        let (addr, server) = serve_all(vec![
+            b"HTTP/1.1 303 See Other\r\nLocation: /home\r\nSet-Cookie: session=abc\r\nContent-Length: 0\r\n\r\n",
+            b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nhi",
+        ]);
+
+        let mut client = Client::new();
+        *client.max_redirects_mut() = 5;
+        let mut jar = CookieJar::new();
+
+        let mut request = RequestBuilder::post("/login".into());
+        request.body_but().extend_from_slice(b"user=me");
+        let response = client.send_with_cookies(&addr, request, &mut jar).unwrap();
+        assert_eq!(response.body(), b"hi");
+
+        let requests = server.join().unwrap();
+        let redirected = Request::from_bytes(&requests[1]).unwrap();
+        assert_eq!(redirected.method(), Method::Get);
+        assert_eq!(redirected.uri(), "/home");
+        assert_eq!(redirected.header().get("Cookie"), Some("session=abc"));
+        assert!(redirected.body().is_empty());
+    }
+
+    #[test]
+    fn test_too_many_redirects() {
+        let redirect: &'static [u8] =
+            b"HTTP/1.1 307 Temporary Redirect\r\nLocation: /\r\nContent-Length: 0\r\n\r\n";
+        let (addr, server) = serve_all(vec![redirect, redirect]);
+
+        let mut client = Client::new();
+        *client.max_redirects_mut() = 1;
+
+        let err = client
+            .send(&addr, RequestBuilder::get("/".into()))
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Other);
+
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_read_response_truncated() {
+        let mut reader: &[u8] = b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nHel";
+        let err = read_response(&mut reader, &mut Vec::new(), 1024, Method::Get).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn test_read_response_head() {
+        let mut reader: &[u8] = b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\n";
+        let response = read_response(&mut reader, &mut Vec::new(), 1024, Method::Head).unwrap();
+
+        assert_eq!(response.header().get("Content-Length"), Some("5"));
+        assert!(response.body().is_empty());
+    }
+
+    #[test]
+    fn test_read_response_keeps_rest() {
+        let mut reader: &[u8] = b"HTTP/1.1 204 No Content\r\n\r\nHTTP/1.1";
+        let mut buf = Vec::new();
+        let response = read_response(&mut reader, &mut buf, 1024, Method::Get).unwrap();
+
+        assert_eq!(response.status(), Status::NoContent);
+        assert_eq!(buf, b"HTTP/1.1");
+    }
+
+    #[test]
+    fn test_read_response_skips_interim() {
+        let mut reader: &[u8] = b"HTTP/1.1 100 Continue\r\n\r\n\
+            HTTP/1.1 103 Early Hints\r\nLink: </a.css>; rel=preload\r\n\r\n\
+            HTTP/1.1 199 Unknown\r\n\r\n\
+            HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok";
+        let response = read_response(&mut reader, &mut Vec::new(), 1024, Method::Get).unwrap();
+
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.body(), b"ok");
+
+        // unknown codes are read as the x00 code of their class
+        let mut reader: &[u8] = b"HTTP/1.1 451 Unavailable\r\nContent-Length: 2\r\n\r\nno";
+        let response = read_response(&mut reader, &mut Vec::new(), 1024, Method::Get).unwrap();
+
+        assert_eq!(response.status(), Status::BadRequest);
+        assert_eq!(response.body(), b"no");
+    }
+
+    /// hands out a single byte per read, like a slow connection
+    struct Trickle<'a>(&'a [u8]);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = self.0.len().min(buf.len()).min(1);
+            buf[..n].copy_from_slice(&self.0[..n]);
+            self.0 = &self.0[n..];
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn test_read_response_trickle() {
+        let mut raw = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n".to_vec();
+        for _ in 0..1000 {
+            raw.extend_from_slice(b"3\r\nabc\r\n");
+        }
+        raw.extend_from_slice(b"0\r\nDigest: x\r\nExpires: 0\r\n\r\nHTTP/1.1");
+
+        // nothing behind the response is read
+        let mut reader = Trickle(&raw);
+        let mut buf = Vec::new();
+        let response = read_response(&mut reader, &mut buf, 8192, Method::Get).unwrap();
+        assert_eq!(response.body(), "abc".repeat(1000).as_bytes());
+        assert_eq!(response.trailers().get("Digest"), Some("x"));
+        assert!(!response.trailers().contains("Expires"));
+        assert!(buf.is_empty());
+        assert_eq!(reader.0, b"HTTP/1.1");
+
+        let err =
+            read_response(&mut Trickle(&raw), &mut Vec::new(), 1024, Method::Get).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_read_response_trailers() {
+        let mut reader: &[u8] =
+            b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\nTrailer: Grpc-Status\r\n\r\n\
+            2\r\nok\r\n0\r\nGrpc-Status: 0\r\n\r\n";
+        let response = read_response(&mut reader, &mut Vec::new(), 1024, Method::Get).unwrap();
+
+        assert_eq!(response.body(), b"ok");
+        assert_eq!(response.trailers().get("Grpc-Status"), Some("0"));
+        assert!(!response.header().contains("Grpc-Status"));
+    }
+}
@@ -0,0 +1,249 @@
+use std::io::{self, Read};
+
+use crate::{Error, HeaderMap, chunked, framing::BodyLength};
+
+/// the body of a response, read as it arrives instead of buffered as a whole, see
+/// `Client::event_stream`
+///
+/// the chunked transfer coding is removed, trailers of a chunked body are read and dropped.
+/// `read` returns `0` once the body ended, a connection closed in the middle of the body fails
+/// with `io::ErrorKind::UnexpectedEof`.
+#[derive(Debug)]
+pub struct BodyReader<R> {
+    reader: R,
+    /// bytes read from `reader` but not returned yet
+    buf: Vec<u8>,
+    state: State,
+    /// the trailer fields received so far, valid for `HeaderMap::new`
+    trailers: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// this many bytes of the body are left
+    Fixed(usize),
+    /// a chunk-size line is expected
+    ChunkSize,
+    /// this many bytes of the current chunk are left
+    ChunkData(usize),
+    /// the CRLF after the data of a chunk is expected
+    ChunkEnd,
+    /// trailer fields are expected up to the empty line
+    Trailers,
+    /// the body ends when the connection is closed
+    UntilEof,
+    Done,
+}
+
+impl<R: Read> BodyReader<R> {
+    /// `buf` holds the bytes already read behind the head of the response
+    pub(crate) fn new(reader: R, buf: Vec<u8>, length: BodyLength) -> Self {
+        let state = match length {
+            BodyLength::Fixed(0) => State::Done,
+            BodyLength::Fixed(len) => State::Fixed(len),
+            BodyLength::Chunked => State::ChunkSize,
+            BodyLength::UntilEof => State::UntilEof,
+        };
+
+        Self {
+            reader,
+            buf,
+            state,
+            trailers: String::new(),
+        }
+    }
+
+    pub fn reader(&self) -> &R {
+        &self.reader
+    }
+
+    /// returns the underlying reader, bytes which were already read from it are lost
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// returns the underlying reader, the bytes read from it behind the body and the trailer
+    /// fields without the forbidden ones, see `chunked::is_forbidden_trailer`
+    pub(crate) fn into_parts(self) -> (R, Vec<u8>, String) {
+        (self.reader, self.buf, self.trailers)
+    }
+
+    /// reads more bytes into `buf`, returns `false` if the reader is at its end
+    fn fill(&mut self) -> io::Result<bool> {
+        let mut chunk = [0_u8; 8192];
+
+        loop {
+            match self.reader.read(&mut chunk) {
+                Ok(0) => return Ok(false),
+                Ok(n) => {
+                    self.buf.extend_from_slice(&chunk[..n]);
+                    return Ok(true);
+                }
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => (),
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// removes a line terminated by CRLF from the start of `buf` and returns it without CRLF
+    fn take_line(&mut self) -> io::Result<Vec<u8>> {
+        loop {
+            match chunked::read_line(&self.buf) {
+                Ok((line, len)) => {
+                    let line = line.to_vec();
+                    self.buf.drain(..len);
+                    return Ok(line);
+                }
+                Err(Error::NotEnoughData) if self.buf.len() > 64 * 1024 => {
+                    return Err(Error::InvalidHeader.into());
+                }
+                Err(Error::NotEnoughData) => {
+                    if !self.fill()? {
+                        return Err(io::ErrorKind::UnexpectedEof.into());
+                    }
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+
+    /// validates the trailer field `line` and keeps it unless it is forbidden
+    fn push_trailer(&mut self, line: &[u8]) -> Result<(), Error> {
+        let line = std::str::from_utf8(line).ok().ok_or(Error::InvalidUtf8)?;
+        let Some((name, value)) = HeaderMap::new(line)?.iter().next() else {
+            return Err(Error::InvalidHeader);
+        };
+
+        if self.trailers.len() + line.len() > 64 * 1024 {
+            return Err(Error::InvalidHeader);
+        }
+
+        if !chunked::is_forbidden_trailer(name) {
+            if !self.trailers.is_empty() {
+                self.trailers.push_str("\r\n");
+            }
+            self.trailers.push_str(name);
+            self.trailers.push_str(": ");
+            self.trailers.push_str(value);
+        }
+
+        Ok(())
+    }
+
+    /// copies up to `max` bytes of the body into `out`
+    fn read_data(&mut self, out: &mut [u8], max: usize) -> io::Result<usize> {
+        if self.buf.is_empty() && !self.fill()? {
+            return Ok(0);
+        }
+
+        let n = out.len().min(max).min(self.buf.len());
+        out[..n].copy_from_slice(&self.buf[..n]);
+        self.buf.drain(..n);
+
+        Ok(n)
+    }
+}
+
+impl<R: Read> Read for BodyReader<R> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        if out.is_empty() {
+            return Ok(0);
+        }
+
+        loop {
+            match self.state {
+                State::Done => return Ok(0),
+                State::UntilEof => return self.read_data(out, usize::MAX),
+                State::Fixed(left) => {
+                    let n = self.read_data(out, left)?;
+                    if n == 0 {
+                        return Err(io::ErrorKind::UnexpectedEof.into());
+                    }
+
+                    self.state = match left - n {
+                        0 => State::Done,
+                        left => State::Fixed(left),
+                    };
+                    return Ok(n);
+                }
+                State::ChunkSize => {
+                    let line = self.take_line()?;
+                    self.state = match chunked::parse_size(&line)? {
+                        0 => State::Trailers,
+                        size => State::ChunkData(size),
+                    };
+                }
+                State::ChunkData(left) => {
+                    let n = self.read_data(out, left)?;
+                    if n == 0 {
+                        return Err(io::ErrorKind::UnexpectedEof.into());
+                    }
+
+                    self.state = match left - n {
+                        0 => State::ChunkEnd,
+                        left => State::ChunkData(left),
+                    };
+                    return Ok(n);
+                }
+                State::ChunkEnd => {
+                    if !self.take_line()?.is_empty() {
+                        return Err(Error::InvalidHeader.into());
+                    }
+                    self.state = State::ChunkSize;
+                }
+                State::Trailers => {
+                    let line = self.take_line()?;
+                    if line.is_empty() {
+                        self.state = State::Done;
+                        continue;
+                    }
+
+                    self.push_trailer(&line)?;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn read_all(raw: &[u8], length: BodyLength) -> io::Result<Vec<u8>> {
+        // the first byte arrives together with the head
+        let mut reader = BodyReader::new(&raw[1..], raw[..1].to_vec(), length);
+        let mut body = Vec::new();
+        reader.read_to_end(&mut body)?;
+        Ok(body)
+    }
+
+    #[test]
+    fn test_read() {
+        assert_eq!(read_all(b"Hello!", BodyLength::Fixed(5)).unwrap(), b"Hello");
+        assert_eq!(read_all(b"Hello", BodyLength::UntilEof).unwrap(), b"Hello");
+
+        let raw = b"5\r\nHello\r\n6;ext=1\r\n World\r\n0\r\nDigest: x\r\nExpires: 0\r\n\r\nrest";
+        assert_eq!(read_all(raw, BodyLength::Chunked).unwrap(), b"Hello World");
+
+        let mut reader = BodyReader::new(&raw[..], Vec::new(), BodyLength::Chunked);
+        reader.read_to_end(&mut Vec::new()).unwrap();
+        let (_, rest, trailers) = reader.into_parts();
+        assert_eq!(rest, b"rest");
+        assert_eq!(trailers, "Digest: x");
+    }
+
+    #[test]
+    fn test_read_truncated() {
+        let err = read_all(b"Hel", BodyLength::Fixed(5)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+        let err = read_all(b"5\r\nHello\r\n", BodyLength::Chunked).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+        let err = read_all(b"2\r\nabc\r\n0\r\n\r\n", BodyLength::Chunked).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        let err = read_all(b"0\r\nDigest\r\n\r\n", BodyLength::Chunked).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,326 @@
+use std::time::{Duration, SystemTime};
+
+use crate::{HeaderMap, RequestBuilder, date};
+
+/// an in-memory cookie store following the rules of RFC 6265
+///
+/// cookies received with `Set-Cookie` are stored with `CookieJar::store` and sent again with
+/// `CookieJar::apply` on later requests to a matching domain and path. `Secure` cookies are
+/// never sent since the client only speaks plain HTTP.
+///
+/// # Examples
+/// ```
+/// use reqse::{HeaderMap, RequestBuilder, client::CookieJar};
+///
+/// let mut jar = CookieJar::new();
+/// let header = HeaderMap::new("Set-Cookie: session=abc; Path=/api\r\n").unwrap();
+/// jar.store("localhost:3000", "/api/login", &header);
+///
+/// let mut request = RequestBuilder::get("/api/users".into());
+/// jar.apply("localhost:3000", &mut request);
+/// assert_eq!(request.header().get("Cookie").unwrap(), "session=abc");
+///
+/// let mut request = RequestBuilder::get("/".into());
+/// jar.apply("localhost:3000", &mut request);
+/// assert!(request.header().get("Cookie").is_none());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CookieJar {
+    cookies: Vec<Cookie>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Cookie {
+    name: String,
+    value: String,
+    /// lowercase domain without leading dot
+    domain: String,
+    /// only sent to exactly `domain`, not its subdomains
+    host_only: bool,
+    path: String,
+    expires: Option<SystemTime>,
+    secure: bool,
+}
+
+impl CookieJar {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.cookies.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cookies.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.cookies.clear();
+    }
+
+    /// returns the value of the cookie `name` which would be sent to `addr` with `uri`
+    pub fn get(&self, addr: &str, uri: &str, name: &str) -> Option<&str> {
+        let host = host(addr);
+        let path = path(uri);
+        let now = SystemTime::now();
+
+        self.cookies
+            .iter()
+            .find(|cookie| cookie.name == name && cookie.matches(&host, path, now))
+            .map(|cookie| cookie.value.as_str())
+    }
+
+    /// stores the cookies of all `Set-Cookie` fields in `header`, which is the header of a
+    /// response to a request sent to `addr` (eg: `localhost:3000`) with `uri`
+    ///
+    /// cookies which are malformed or set a domain the host does not belong to are ignored,
+    /// cookies which expired remove the stored cookie with the same name, domain and path.
+    pub fn store(&mut self, addr: &str, uri: &str, header: &HeaderMap) {
+        let host = host(addr);
+        let now = SystemTime::now();
+
+        for (_, value) in header
+            .iter()
+            .filter(|(key, _)| key.eq_ignore_ascii_case("Set-Cookie"))
+        {
+            let Some(cookie) = Cookie::parse(value, &host, uri, now) else {
+                continue;
+            };
+
+            self.cookies.retain(|c| {
+                !(c.name == cookie.name && c.domain == cookie.domain && c.path == cookie.path)
+            });
+
+            if cookie.expires.is_none_or(|expires| expires > now) {
+                self.cookies.push(cookie);
+            }
+        }
+
+        self.cookies
+            .retain(|c| c.expires.is_none_or(|expires| expires > now));
+    }
+
+    /// adds the cookies matching `addr` and the uri of `request` to its `Cookie` header
+    pub fn apply(&self, addr: &str, request: &mut RequestBuilder) {
+        let host = host(addr);
+        let path = path(request.uri());
+        let now = SystemTime::now();
+
+        let mut cookies = self
+            .cookies
+            .iter()
+            .filter(|cookie| cookie.matches(&host, path, now))
+            .collect::<Vec<_>>();
+
+        if cookies.is_empty() {
+            return;
+        }
+
+        // cookies with longer paths are listed first, see RFC 6265 section 5.4
+        cookies.sort_by_key(|cookie| std::cmp::Reverse(cookie.path.len()));
+
+        let cookies = cookies
+            .iter()
+            .map(|cookie| format!("{}={}", cookie.name, cookie.value))
+            .collect::<Vec<_>>()
+            .join("; ");
+
+        let existing = request
+            .header()
+            .keys()
+            .find(|key| key.eq_ignore_ascii_case("Cookie"))
+            .cloned();
+
+        match existing.and_then(|key| request.header_mut().get_mut(&key)) {
+            Some(value) => {
+                value.push_str("; ");
+                value.push_str(&cookies);
+            }
+            None => {
+                request.header_mut().insert("Cookie".to_owned(), cookies);
+            }
+        }
+    }
+}
+
+impl Cookie {
+    fn parse(set_cookie: &str, host: &str, uri: &str, now: SystemTime) -> Option<Self> {
+        let mut attributes = set_cookie.split(';');
+
+        let (name, value) = attributes.next()?.split_once('=')?;
+        let name = name.trim();
+        let value = value.trim().trim_matches('"');
+
+        if name.is_empty() {
+            return None;
+        }
+
+        let mut cookie = Cookie {
+            name: name.to_owned(),
+            value: value.to_owned(),
+            domain: host.to_owned(),
+            host_only: true,
+            path: default_path(path(uri)).to_owned(),
+            expires: None,
+            secure: false,
+        };
+
+        let mut max_age = None;
+
+        for attribute in attributes {
+            let (key, value) = attribute.split_once('=').unwrap_or((attribute, ""));
+            let key = key.trim();
+            let value = value.trim();
+
+            if key.eq_ignore_ascii_case("Domain") && !value.is_empty() {
+                let domain = value.trim_start_matches('.').to_ascii_lowercase();
+                if !domain_matches(host, &domain) {
+                    return None;
+                }
+                cookie.domain = domain;
+                cookie.host_only = false;
+            } else if key.eq_ignore_ascii_case("Path") && value.starts_with('/') {
+                cookie.path = value.to_owned();
+            } else if key.eq_ignore_ascii_case("Max-Age") {
+                max_age = value.parse::<i64>().ok();
+            } else if key.eq_ignore_ascii_case("Expires") {
+                cookie.expires = cookie.expires.or(date::parse_http_date(value));
+            } else if key.eq_ignore_ascii_case("Secure") {
+                cookie.secure = true;
+            }
+        }
+
+        // Max-Age takes precedence over Expires
+        match max_age {
+            Some(age) if age <= 0 => cookie.expires = Some(SystemTime::UNIX_EPOCH),
+            Some(age) => cookie.expires = now.checked_add(Duration::from_secs(age as u64)),
+            None => (),
+        }
+
+        Some(cookie)
+    }
+
+    fn matches(&self, host: &str, path: &str, now: SystemTime) -> bool {
+        let domain = if self.host_only {
+            host == self.domain
+        } else {
+            domain_matches(host, &self.domain)
+        };
+
+        domain
+            && path_matches(path, &self.path)
+            && !self.secure
+            && self.expires.is_none_or(|expires| expires > now)
+    }
+}
+
+/// returns the lowercase host of `addr` without the port
+fn host(addr: &str) -> String {
+    let host = match addr.rfind(':') {
+        Some(i) if !addr[i..].contains(']') => &addr[..i],
+        _ => addr,
+    };
+
+    host.to_ascii_lowercase()
+}
+
+/// returns the path of `uri` without query and fragment
+fn path(uri: &str) -> &str {
+    let path = uri.split(['?', '#']).next().unwrap_or(uri);
+    if path.starts_with('/') { path } else { "/" }
+}
+
+/// the path a cookie without `Path` attribute is scoped to, see RFC 6265 section 5.1.4
+fn default_path(path: &str) -> &str {
+    match path.rfind('/') {
+        Some(0) | None => "/",
+        Some(i) => &path[..i],
+    }
+}
+
+/// see RFC 6265 section 5.1.3
+fn domain_matches(host: &str, domain: &str) -> bool {
+    host == domain
+        || (host.ends_with(domain)
+            && host[..host.len() - domain.len()].ends_with('.')
+            && host.parse::<std::net::IpAddr>().is_err())
+}
+
+/// see RFC 6265 section 5.1.4
+fn path_matches(path: &str, cookie_path: &str) -> bool {
+    path == cookie_path
+        || (path.starts_with(cookie_path)
+            && (cookie_path.ends_with('/') || path[cookie_path.len()..].starts_with('/')))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn store(jar: &mut CookieJar, addr: &str, uri: &str, set_cookie: &str) {
+        let header = format!("Set-Cookie: {}\r\n", set_cookie);
+        jar.store(addr, uri, &HeaderMap::new(&header).unwrap());
+    }
+
+    #[test]
+    fn test_domain_matching() {
+        let mut jar = CookieJar::new();
+        store(
+            &mut jar,
+            "api.example.com:80",
+            "/",
+            "a=1; Domain=.example.com",
+        );
+        store(&mut jar, "api.example.com:80", "/", "b=2");
+        store(&mut jar, "api.example.com:80", "/", "c=3; Domain=other.com");
+
+        assert_eq!(jar.len(), 2);
+        assert_eq!(jar.get("www.example.com:80", "/", "a"), Some("1"));
+        assert_eq!(jar.get("www.example.com:80", "/", "b"), None);
+        assert_eq!(jar.get("api.example.com:8080", "/", "b"), Some("2"));
+        assert_eq!(jar.get("badexample.com:80", "/", "a"), None);
+    }
+
+    #[test]
+    fn test_path_matching() {
+        let mut jar = CookieJar::new();
+        store(&mut jar, "localhost:80", "/docs/page", "a=1");
+        store(&mut jar, "localhost:80", "/", "b=2; Path=/docs/page");
+
+        assert_eq!(jar.get("localhost:80", "/docs", "a"), Some("1"));
+        assert_eq!(jar.get("localhost:80", "/docs/x?y", "a"), Some("1"));
+        assert_eq!(jar.get("localhost:80", "/docsx", "a"), None);
+
+        let mut request = RequestBuilder::get("/docs/page".into());
+        jar.apply("localhost:80", &mut request);
+        assert_eq!(request.header().get("Cookie").unwrap(), "b=2; a=1");
+    }
+
+    #[test]
+    fn test_expiry() {
+        let mut jar = CookieJar::new();
+        store(&mut jar, "localhost:80", "/", "a=1; Max-Age=60");
+        store(
+            &mut jar,
+            "localhost:80",
+            "/",
+            "b=2; Expires=Wed, 21 Oct 2015 07:28:00 GMT",
+        );
+        assert_eq!(jar.get("localhost:80", "/", "a"), Some("1"));
+        assert_eq!(jar.len(), 1);
+
+        store(&mut jar, "localhost:80", "/", "a=; Max-Age=0");
+        assert!(jar.is_empty());
+    }
+
+    #[test]
+    fn test_secure_cookies_are_not_sent() {
+        let mut jar = CookieJar::new();
+        store(&mut jar, "localhost:80", "/", "a=1; Secure; HttpOnly");
+
+        let mut request = RequestBuilder::get("/".into());
+        jar.apply("localhost:80", &mut request);
+        assert!(request.header().is_empty());
+    }
+}
@@ -0,0 +1,442 @@
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+    net::TcpStream,
+    sync::{Condvar, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    Method, OwnedResponse, RequestBuilder, Version,
+    client::{Client, read_response, set_default_header},
+    connection,
+    framing::{self, BodyLength},
+};
+
+/// a client which keeps idle keep-alive connections open and reuses them for later requests to
+/// the same `host:port`
+///
+/// the pool is meant to be shared between threads (eg: behind an `Arc`), a thread sending a
+/// request while all connections to the host are in use waits until one is returned.
+///
+/// # Examples
+/// ```no_run
+/// use reqse::{RequestBuilder, client::ClientPool};
+///
+/// let pool = ClientPool::new();
+///
+/// // the second request reuses the connection of the first one
+/// let a = pool.send("localhost:3000", RequestBuilder::get("/a".into())).unwrap();
+/// let b = pool.send("localhost:3000", RequestBuilder::get("/b".into())).unwrap();
+/// ```
+#[derive(Debug)]
+pub struct ClientPool {
+    client: Client,
+    idle_timeout: Duration,
+    max_connections_per_host: usize,
+    hosts: Mutex<HashMap<String, Host>>,
+    released: Condvar,
+}
+
+#[derive(Debug, Default)]
+struct Host {
+    /// number of open connections, idle ones included
+    open: usize,
+    idle: Vec<Connection>,
+}
+
+#[derive(Debug)]
+struct Connection {
+    stream: TcpStream,
+    /// bytes read from the stream which do not belong to a response yet
+    buf: Vec<u8>,
+    idle_since: Instant,
+}
+
+impl ClientPool {
+    pub fn new() -> Self {
+        Self::with_client(Client::new())
+    }
+
+    /// creates a pool which opens its connections with the timeouts and limits of `client`
+    pub fn with_client(client: Client) -> Self {
+        Self {
+            client,
+            idle_timeout: Duration::from_secs(30),
+            max_connections_per_host: 8,
+            hosts: Mutex::new(HashMap::new()),
+            released: Condvar::new(),
+        }
+    }
+
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    pub fn client_mut(&mut self) -> &mut Client {
+        &mut self.client
+    }
+
+    /// how long a connection may stay idle in the pool before it is closed
+    pub fn idle_timeout(&self) -> Duration {
+        self.idle_timeout
+    }
+
+    pub fn idle_timeout_mut(&mut self) -> &mut Duration {
+        &mut self.idle_timeout
+    }
+
+    /// maximum number of connections (in use and idle) open to a single `host:port`
+    pub fn max_connections_per_host(&self) -> usize {
+        self.max_connections_per_host
+    }
+
+    pub fn max_connections_per_host_mut(&mut self) -> &mut usize {
+        &mut self.max_connections_per_host
+    }
+
+    /// number of idle connections to `addr` currently kept in the pool
+    pub fn idle_connections(&self, addr: &str) -> usize {
+        self.lock()
+            .get(addr)
+            .map(|host| host.idle.len())
+            .unwrap_or(0)
+    }
+
+    /// sends `request` to `addr` (eg: `localhost:3000`) over an idle connection from the pool or
+    /// a new one
+    ///
+    /// the `Host` header is set to `addr` unless the request already has one. If a reused
+    /// connection turns out to be closed by the server before any byte of the response arrived
+    /// an idempotent request is sent again exactly once over a new connection. A `POST` is not,
+    /// the server may already have acted on it (RFC 9112 section 9.3.1).
+    ///
+    /// # Error
+    /// see `Client::send`
+    pub fn send(&self, addr: &str, mut request: RequestBuilder) -> io::Result<OwnedResponse> {
+        set_default_header(&mut request, "Host", addr);
+
+        let fields = request
+            .header()
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()));
+        let close = connection::has_option(fields, "close");
+
+        let method = request.method();
+        let raw_request = request.try_finish()?;
+
+        let (mut connection, reused) = self.acquire(addr)?;
+
+        let result = match self.exchange(&mut connection, &raw_request, method) {
+            Err(err) if reused && is_idempotent(method) && is_stale(&err, &connection) => {
+                self.release(addr, connection, false);
+
+                let (mut connection, _) = self.acquire_new(addr)?;
+                let result = self.exchange(&mut connection, &raw_request, method);
+                return self.finish(addr, connection, result, method, close);
+            }
+            result => result,
+        };
+
+        self.finish(addr, connection, result, method, close)
+    }
+
+    /// writes `raw_request` to the connection and reads the response
+    fn exchange(
+        &self,
+        connection: &mut Connection,
+        raw_request: &[u8],
+        method: Method,
+    ) -> io::Result<OwnedResponse> {
+        connection.stream.write_all(raw_request)?;
+        connection.stream.flush()?;
+
+        read_response(
+            &mut connection.stream,
+            &mut connection.buf,
+            self.client.max_response_len(),
+            method,
+        )
+    }
+
+    /// returns the connection to the pool if it can be reused after `result`
+    fn finish(
+        &self,
+        addr: &str,
+        connection: Connection,
+        result: io::Result<OwnedResponse>,
+        method: Method,
+        close: bool,
+    ) -> io::Result<OwnedResponse> {
+        let reusable = match &result {
+            Ok(response) => !close && reusable(response, method),
+            Err(_) => false,
+        };
+
+        self.release(addr, connection, reusable);
+
+        result
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, Host>> {
+        self.hosts.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// takes an idle connection from the pool or opens a new one if the host limit allows it,
+    /// waits for a connection to be released otherwise
+    ///
+    /// the returned bool tells whether the connection was reused
+    fn acquire(&self, addr: &str) -> io::Result<(Connection, bool)> {
+        let mut hosts = self.lock();
+
+        loop {
+            let host = hosts.entry(addr.to_owned()).or_default();
+
+            let before = host.idle.len();
+            host.idle
+                .retain(|conn| conn.idle_since.elapsed() < self.idle_timeout);
+            host.open -= before - host.idle.len();
+
+            if let Some(connection) = host.idle.pop() {
+                return Ok((connection, true));
+            }
+
+            if host.open < self.max_connections_per_host.max(1) {
+                host.open += 1;
+                drop(hosts);
+                return self.connect(addr).map(|connection| (connection, false));
+            }
+
+            hosts = self
+                .released
+                .wait(hosts)
+                .unwrap_or_else(|err| err.into_inner());
+        }
+    }
+
+    /// opens a new connection regardless of idle connections in the pool, used to retry a
+    /// request after a reused connection turned out to be stale
+    fn acquire_new(&self, addr: &str) -> io::Result<(Connection, bool)> {
+        let mut hosts = self.lock();
+
+        loop {
+            let host = hosts.entry(addr.to_owned()).or_default();
+
+            if host.open < self.max_connections_per_host.max(1) {
+                host.open += 1;
+                drop(hosts);
+                return self.connect(addr).map(|connection| (connection, false));
+            }
+
+            // idle connections are likely stale as well, close one to make room
+            if host.idle.pop().is_some() {
+                host.open -= 1;
+                continue;
+            }
+
+            hosts = self
+                .released
+                .wait(hosts)
+                .unwrap_or_else(|err| err.into_inner());
+        }
+    }
+
+    /// opens a new connection, the caller already counted it as open
+    fn connect(&self, addr: &str) -> io::Result<Connection> {
+        match self.client.connect(addr) {
+            Ok(stream) => Ok(Connection {
+                stream,
+                buf: Vec::new(),
+                idle_since: Instant::now(),
+            }),
+            Err(err) => {
+                self.lock().entry(addr.to_owned()).or_default().open -= 1;
+                self.released.notify_one();
+                Err(err)
+            }
+        }
+    }
+
+    fn release(&self, addr: &str, mut connection: Connection, reusable: bool) {
+        let mut hosts = self.lock();
+        let host = hosts.entry(addr.to_owned()).or_default();
+
+        // left over bytes mean the server sent more than one response, the connection is out of
+        // sync with our requests
+        if reusable && connection.buf.is_empty() {
+            connection.idle_since = Instant::now();
+            host.idle.push(connection);
+        } else {
+            host.open -= 1;
+        }
+
+        drop(hosts);
+        self.released.notify_one();
+    }
+}
+
+impl Default for ClientPool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// whether a request with `method` may be sent again automatically
+fn is_idempotent(method: Method) -> bool {
+    matches!(
+        method,
+        Method::Get | Method::Head | Method::Put | Method::Delete | Method::Options
+    )
+}
+
+/// whether a reused connection failed because the server closed it while it was idle
+fn is_stale(err: &io::Error, connection: &Connection) -> bool {
+    let closed = matches!(
+        err.kind(),
+        io::ErrorKind::UnexpectedEof
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::BrokenPipe
+    );
+
+    closed && connection.buf.is_empty()
+}
+
+/// whether the server allows another request on the connection after `response` to a request
+/// with `method`
+fn reusable(response: &OwnedResponse, method: Method) -> bool {
+    let header = response.header();
+
+    let until_eof = matches!(
+        framing::response_body_length(method, response.status(), &header),
+        Ok(BodyLength::UntilEof) | Err(_)
+    );
+
+    if until_eof {
+        return false;
+    }
+
+    match response.version() {
+        Version::Http0 => connection::has_option(header.iter(), "keep-alive"),
+        _ => !connection::has_option(header.iter(), "close"),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Request;
+    use std::{
+        io::Read,
+        net::TcpListener,
+        sync::{
+            Arc,
+            atomic::{AtomicUsize, Ordering},
+        },
+        thread,
+    };
+
+    /// serves `Hello` on every request, closes each connection after `requests_per_connection`
+    /// requests and counts the accepted connections
+    fn serve(requests_per_connection: usize) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let accepted = Arc::new(AtomicUsize::new(0));
+        let counter = accepted.clone();
+
+        thread::spawn(move || {
+            for connection in listener.incoming() {
+                let mut connection = connection.unwrap();
+                counter.fetch_add(1, Ordering::SeqCst);
+
+                thread::spawn(move || {
+                    let mut buf = Vec::new();
+                    let mut chunk = [0_u8; 1024];
+
+                    for _ in 0..requests_per_connection {
+                        let len = loop {
+                            if let Ok((_, len)) = Request::parse(&buf) {
+                                break len;
+                            }
+                            match connection.read(&mut chunk) {
+                                Ok(0) | Err(_) => return,
+                                Ok(n) => buf.extend_from_slice(&chunk[..n]),
+                            }
+                        };
+                        buf.drain(..len);
+
+                        connection
+                            .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nHello")
+                            .unwrap();
+                    }
+                });
+            }
+        });
+
+        (addr, accepted)
+    }
+
+    #[test]
+    fn test_reuse_connection() {
+        let (addr, accepted) = serve(usize::MAX);
+        let pool = ClientPool::new();
+
+        for _ in 0..3 {
+            let response = pool.send(&addr, RequestBuilder::get("/".into())).unwrap();
+            assert_eq!(response.body(), b"Hello");
+        }
+
+        assert_eq!(accepted.load(Ordering::SeqCst), 1);
+        assert_eq!(pool.idle_connections(&addr), 1);
+    }
+
+    #[test]
+    fn test_connection_close_is_respected() {
+        let (addr, accepted) = serve(usize::MAX);
+        let pool = ClientPool::new();
+
+        for _ in 0..2 {
+            let mut request = RequestBuilder::get("/".into());
+            request
+                .header_mut()
+                .insert("Connection".to_owned(), "close".to_owned());
+            pool.send(&addr, request).unwrap();
+        }
+
+        assert_eq!(accepted.load(Ordering::SeqCst), 2);
+        assert_eq!(pool.idle_connections(&addr), 0);
+    }
+
+    #[test]
+    fn test_retry_stale_connection() {
+        let (addr, accepted) = serve(1);
+        let pool = ClientPool::new();
+
+        pool.send(&addr, RequestBuilder::get("/".into())).unwrap();
+
+        // the server closed the pooled connection after the first request
+        thread::sleep(Duration::from_millis(50));
+        let response = pool.send(&addr, RequestBuilder::get("/".into())).unwrap();
+
+        assert_eq!(response.body(), b"Hello");
+        assert_eq!(accepted.load(Ordering::SeqCst), 2);
+
+        // a POST may have been acted on, the error is returned instead
+        thread::sleep(Duration::from_millis(50));
+        let request = RequestBuilder::post("/".into());
+        assert!(pool.send(&addr, request).is_err());
+        assert_eq!(accepted.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_idle_timeout() {
+        let (addr, accepted) = serve(usize::MAX);
+        let mut pool = ClientPool::new();
+        *pool.idle_timeout_mut() = Duration::ZERO;
+
+        pool.send(&addr, RequestBuilder::get("/".into())).unwrap();
+        pool.send(&addr, RequestBuilder::get("/".into())).unwrap();
+
+        assert_eq!(accepted.load(Ordering::SeqCst), 2);
+    }
+}
@@ -0,0 +1,234 @@
+use std::io;
+
+use crate::{Method, OwnedResponse, RequestBuilder, Status};
+
+/// returns the `Location` of `response` if it is a redirect the client should follow
+pub(crate) fn location(response: &OwnedResponse) -> Option<&str> {
+    let redirect = matches!(
+        response.status(),
+        Status::MovedPermanently
+            | Status::Found
+            | Status::SeeOther
+            | Status::TemporaryRedirect
+            | Status::PermanentRedirect
+    );
+
+    if !redirect {
+        return None;
+    }
+
+    response.header().get("Location")
+}
+
+/// resolves `location` against the request which was sent to `addr` with `uri`
+///
+/// returns the address and uri the redirected request has to be sent to
+pub(crate) fn resolve(addr: &str, uri: &str, location: &str) -> io::Result<(String, String)> {
+    let location = location.split('#').next().unwrap_or(location).trim();
+
+    if let Some((scheme, rest)) = location.split_once("://")
+        && is_scheme(scheme)
+    {
+        // schemes are case-insensitive, see RFC 3986 section 3.1
+        if !scheme.eq_ignore_ascii_case("http") {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "redirect to an unsupported scheme",
+            ));
+        }
+
+        let (authority, path) = match rest.find(['/', '?']) {
+            Some(i) => rest.split_at(i),
+            None => (rest, "/"),
+        };
+
+        if authority.is_empty() || authority.contains('@') {
+            return Err(invalid_location());
+        }
+
+        let has_port = match authority.rfind(':') {
+            Some(i) => !authority[i..].contains(']'),
+            None => false,
+        };
+
+        let addr = if has_port {
+            authority.to_owned()
+        } else {
+            format!("{}:80", authority)
+        };
+
+        let path = if path.starts_with('?') {
+            format!("/{}", path)
+        } else {
+            path.to_owned()
+        };
+
+        return Ok((addr, remove_dot_segments(&path)));
+    }
+
+    if location.starts_with("//") {
+        return resolve(addr, uri, &format!("http:{}", location));
+    }
+
+    if location.starts_with('/') {
+        return Ok((addr.to_owned(), remove_dot_segments(location)));
+    }
+
+    if location.is_empty() {
+        return Err(invalid_location());
+    }
+
+    // relative reference, resolved against the directory of the current path
+    let path = uri.split(['?', '#']).next().unwrap_or(uri);
+    let dir = &path[..path.rfind('/').map(|i| i + 1).unwrap_or(0)];
+    let dir = if dir.is_empty() { "/" } else { dir };
+
+    let uri = if location.starts_with('?') {
+        format!("{}{}", path, location)
+    } else {
+        format!("{}{}", dir, location)
+    };
+
+    Ok((addr.to_owned(), remove_dot_segments(&uri)))
+}
+
+/// whether `scheme` is a valid uri scheme, see RFC 3986 section 3.1
+fn is_scheme(scheme: &str) -> bool {
+    scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'+' | b'-' | b'.'))
+}
+
+/// removes the `.` and `..` segments from the path of the absolute `uri`, the query is kept as
+/// it is, see RFC 3986 section 5.2.4
+fn remove_dot_segments(uri: &str) -> String {
+    let (path, query) = match uri.find('?') {
+        Some(i) => uri.split_at(i),
+        None => (uri, ""),
+    };
+
+    let segments = path.split('/').skip(1).collect::<Vec<_>>();
+    let mut output = Vec::with_capacity(segments.len());
+
+    for (i, segment) in segments.iter().enumerate() {
+        match *segment {
+            "." => (),
+            ".." => {
+                output.pop();
+            }
+            segment => output.push(segment),
+        }
+
+        // a trailing dot segment still refers to a directory
+        if i == segments.len() - 1 && matches!(*segment, "." | "..") {
+            output.push("");
+        }
+    }
+
+    format!("/{}{}", output.join("/"), query)
+}
+
+/// rewrites `request` for following a redirect with `status`, see RFC 9110 section 15.4
+///
+/// `303 See Other` turns every request but `HEAD` into a body-less `GET`, `301` and `302` only
+/// do so for `POST` (as every browser does), `307` and `308` keep method and body.
+pub(crate) fn rewrite(request: &mut RequestBuilder, status: Status) {
+    let to_get = match status {
+        Status::SeeOther => request.method() != Method::Head,
+        Status::MovedPermanently | Status::Found => request.method() == Method::Post,
+        _ => false,
+    };
+
+    if !to_get {
+        return;
+    }
+
+    *request.method_mut() = Method::Get;
+    request.body_but().clear();
+    request.header_mut().retain(|key, _| {
+        !["Content-Length", "Content-Type", "Transfer-Encoding"]
+            .iter()
+            .any(|k| k.eq_ignore_ascii_case(key))
+    });
+}
+
+fn invalid_location() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "invalid Location header")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn resolved(location: &str) -> (String, String) {
+        resolve("localhost:3000", "/a/b?x=1", location).unwrap()
+    }
+
+    #[test]
+    fn test_resolve() {
+        let same = |uri: &str| ("localhost:3000".to_owned(), uri.to_owned());
+
+        assert_eq!(resolved("/c"), same("/c"));
+        assert_eq!(resolved("c#top"), same("/a/c"));
+        assert_eq!(resolved("?y=2"), same("/a/b?y=2"));
+        assert_eq!(
+            resolved("http://example.com/x"),
+            ("example.com:80".to_owned(), "/x".to_owned())
+        );
+        assert_eq!(
+            resolved("//[::1]:8080"),
+            ("[::1]:8080".to_owned(), "/".to_owned())
+        );
+        assert_eq!(
+            resolved("HTTP://Example.com?q"),
+            ("Example.com:80".to_owned(), "/?q".to_owned())
+        );
+        assert!(resolve("localhost:3000", "/", "https://example.com/").is_err());
+        assert!(resolve("localhost:3000", "/", "HTTPS://example.com/").is_err());
+        assert_eq!(
+            resolved("/go?to=http://example.com"),
+            same("/go?to=http://example.com")
+        );
+    }
+
+    #[test]
+    fn test_resolve_dot_segments() {
+        let same = |uri: &str| ("localhost:3000".to_owned(), uri.to_owned());
+
+        assert_eq!(resolved("../c"), same("/c"));
+        assert_eq!(resolved("./c/./d"), same("/a/c/d"));
+        assert_eq!(resolved(".."), same("/"));
+        assert_eq!(resolved("../../../c?x=../y"), same("/c?x=../y"));
+        assert_eq!(resolved("."), same("/a/"));
+        assert_eq!(resolved("/a/b/../c/.."), same("/a/"));
+        assert_eq!(
+            resolved("http://example.com/a/./b/../c"),
+            ("example.com:80".to_owned(), "/a/c".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_rewrite() {
+        let mut request = RequestBuilder::post("/".into());
+        request.body_but().extend_from_slice(b"data");
+        request
+            .header_mut()
+            .insert("Content-Type".to_owned(), "text/plain".to_owned());
+
+        let mut kept = request.clone();
+        rewrite(&mut kept, Status::TemporaryRedirect);
+        assert_eq!(kept, request);
+
+        rewrite(&mut request, Status::Found);
+        assert_eq!(request.method(), Method::Get);
+        assert!(request.body().is_empty());
+        assert!(request.header().is_empty());
+
+        let mut request = RequestBuilder::put("/".into());
+        rewrite(&mut request, Status::MovedPermanently);
+        assert_eq!(request.method(), Method::Put);
+        rewrite(&mut request, Status::SeeOther);
+        assert_eq!(request.method(), Method::Get);
+    }
+}
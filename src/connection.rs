@@ -0,0 +1,137 @@
+//! the `Connection` header field, see RFC 9112 section 9
+
+use std::collections::HashMap;
+
+use crate::{Request, ResponseBuilder, Version};
+
+/// decides whether the connection can be reused after `response` was sent as answer to
+/// `request`
+///
+/// HTTP/1.1 keeps connections open unless either side lists `close` in `Connection`, HTTP/1.0
+/// closes them unless the client lists `keep-alive`. The server uses this for every response,
+/// a handler can close the connection by setting `Connection: close`.
+///
+/// # Examples
+/// ```
+/// use reqse::{Request, ResponseBuilder, should_keep_alive};
+///
+/// let request = Request::from_bytes(b"GET / HTTP/1.1\r\n\r\n").unwrap();
+/// assert!(should_keep_alive(&request, &ResponseBuilder::ok()));
+///
+/// let mut response = ResponseBuilder::ok();
+/// response.header_mut().insert("Connection".into(), "close".into());
+/// assert!(!should_keep_alive(&request, &response));
+///
+/// let request = Request::from_bytes(b"GET / HTTP/1.0\r\n\r\n").unwrap();
+/// assert!(!should_keep_alive(&request, &ResponseBuilder::ok()));
+///
+/// let request = Request::from_bytes(b"GET / HTTP/1.0\r\nConnection: Keep-Alive\r\n\r\n").unwrap();
+/// assert!(should_keep_alive(&request, &ResponseBuilder::ok()));
+/// ```
+pub fn should_keep_alive(request: &Request, response: &ResponseBuilder) -> bool {
+    let response_header = response
+        .header()
+        .iter()
+        .map(|(key, value)| (key.as_str(), value.as_str()));
+
+    if has_option(request.header().iter(), "close") || has_option(response_header, "close") {
+        return false;
+    }
+
+    match request.version() {
+        Version::Http0 => has_option(request.header().iter(), "keep-alive"),
+        _ => true,
+    }
+}
+
+/// whether the comma separated token list `value` contains `token` (case-insensitive)
+pub(crate) fn has_token(value: &str, token: &str) -> bool {
+    value
+        .split(',')
+        .any(|t| t.trim().eq_ignore_ascii_case(token))
+}
+
+/// whether one of the `Connection` fields in `header` lists `option`
+pub(crate) fn has_option<'a>(
+    mut header: impl Iterator<Item = (&'a str, &'a str)>,
+    option: &str,
+) -> bool {
+    header.any(|(key, value)| key.eq_ignore_ascii_case("Connection") && has_token(value, option))
+}
+
+/// the hop-by-hop fields a builder leaves out because `Connection` names them
+///
+/// `close` and `keep-alive` are connection options rather than field names, so a `Keep-Alive`
+/// field going along with `Connection: keep-alive` is still sent. The same goes for the
+/// `Upgrade` field, which is always named by the `upgrade` option
+pub(crate) fn nominated_fields(header: &HashMap<String, String>) -> Vec<&str> {
+    header
+        .iter()
+        .filter(|(key, _)| key.eq_ignore_ascii_case("Connection"))
+        .flat_map(|(_, value)| value.split(','))
+        .map(str::trim)
+        .filter(|name| {
+            !name.is_empty()
+                && !name.eq_ignore_ascii_case("close")
+                && !name.eq_ignore_ascii_case("keep-alive")
+                && !name.eq_ignore_ascii_case("upgrade")
+                && !name.eq_ignore_ascii_case("Connection")
+        })
+        .collect()
+}
+
+/// whether the field `key` of a builder is named in its `Connection` field
+pub(crate) fn is_nominated(nominated: &[&str], key: &str) -> bool {
+    nominated.iter().any(|name| name.eq_ignore_ascii_case(key))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{RequestBuilder, ResponseBuilder, Version};
+
+    #[test]
+    fn test_strip_nominated_fields() {
+        let mut response = ResponseBuilder::ok();
+        let header = response.header_mut();
+        header.insert("Connection".to_owned(), "close, X-Hop".to_owned());
+        header.insert("x-hop".to_owned(), "1".to_owned());
+
+        assert_eq!(
+            response.finish(),
+            b"HTTP/1.1 200 OK\r\nConnection: close, X-Hop\r\nContent-Length: 0\r\n\r\n"
+        );
+    }
+
+    #[test]
+    fn test_http0_never_chunked() {
+        let mut request = RequestBuilder::post("/".to_owned());
+        *request.version_mut() = Version::Http0;
+        request.body_but().extend_from_slice(b"ping");
+        request
+            .header_mut()
+            .insert("Transfer-Encoding".to_owned(), "chunked".to_owned());
+        request
+            .trailers_mut()
+            .insert("Checksum".to_owned(), "1".to_owned());
+
+        assert_eq!(
+            request.finish(),
+            b"POST / HTTP/1.0\r\nContent-Length: 4\r\n\r\nping"
+        );
+
+        let mut response = ResponseBuilder::ok();
+        *response.version_mut() = Version::Http0;
+        response.body_mut().extend_from_slice(b"hello");
+        response
+            .header_mut()
+            .insert("transfer-encoding".to_owned(), "chunked".to_owned());
+        response
+            .trailers_mut()
+            .insert("Checksum".to_owned(), "1".to_owned());
+
+        assert_eq!(
+            response.finish(),
+            b"HTTP/1.0 200 OK\r\nContent-Length: 5\r\n\r\nhello"
+        );
+    }
+}
@@ -1,10 +1,14 @@
-use std::fmt::Display;
+use std::{fmt::Display, io};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Error {
+    InvalidFrame,
     InvalidHeader,
+    InvalidUri,
     InvalidUtf8,
     NotEnoughData,
+    ReadUntilEof,
+    TooLarge,
 }
 
 impl Error {
@@ -19,9 +23,13 @@ impl Error {
     /// ```
     pub fn to_static_str(&self) -> &'static str {
         match self {
+            Error::InvalidFrame => "invalid frame",
             Error::InvalidHeader => "invalid header",
+            Error::InvalidUri => "invalid uri",
             Error::InvalidUtf8 => "invalid utf-8",
             Error::NotEnoughData => "not enough data",
+            Error::ReadUntilEof => "body ends when the connection is closed",
+            Error::TooLarge => "message too large",
         }
     }
 }
@@ -31,3 +39,11 @@ impl Display for Error {
         write!(f, "{}", self.to_static_str())
     }
 }
+
+impl std::error::Error for Error {}
+
+impl From<Error> for io::Error {
+    fn from(err: Error) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, err)
+    }
+}
@@ -0,0 +1,142 @@
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    fmt,
+    sync::Arc,
+};
+
+/// a map holding at most one value per type, used to pass typed data from middleware to
+/// handlers through `Request::extensions_mut`
+///
+/// values are reference counted, so cloning a request shares its extensions instead of cloning
+/// every value. Two maps are equal if they hold the very same values.
+///
+/// # Examples
+/// ```
+/// use reqse::Extensions;
+///
+/// #[derive(Debug, PartialEq)]
+/// struct UserId(u64);
+///
+/// let mut extensions = Extensions::new();
+/// extensions.insert(UserId(42));
+///
+/// assert_eq!(extensions.get::<UserId>(), Some(&UserId(42)));
+/// assert_eq!(extensions.get::<String>(), None);
+/// ```
+#[derive(Clone, Default)]
+pub struct Extensions {
+    map: HashMap<TypeId, Entry>,
+}
+
+#[derive(Clone)]
+struct Entry {
+    type_name: &'static str,
+    value: Arc<dyn Any + Send + Sync>,
+}
+
+impl Extensions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// inserts `value`, returning the previous value of type `T`
+    ///
+    /// the previous value may still be shared with a clone of this map, use `Arc::try_unwrap` to
+    /// take it out
+    pub fn insert<T: Send + Sync + 'static>(&mut self, value: T) -> Option<Arc<T>> {
+        let entry = Entry {
+            type_name: std::any::type_name::<T>(),
+            value: Arc::new(value),
+        };
+
+        self.map
+            .insert(TypeId::of::<T>(), entry)
+            .and_then(|entry| entry.value.downcast().ok())
+    }
+
+    pub fn get<T: Send + Sync + 'static>(&self) -> Option<&T> {
+        self.map
+            .get(&TypeId::of::<T>())
+            .and_then(|entry| entry.value.downcast_ref())
+    }
+
+    /// removes the value of type `T` and returns it
+    ///
+    /// like for `Extensions::insert` the value may still be shared with a clone of this map
+    pub fn remove<T: Send + Sync + 'static>(&mut self) -> Option<Arc<T>> {
+        self.map
+            .remove(&TypeId::of::<T>())
+            .and_then(|entry| entry.value.downcast().ok())
+    }
+
+    pub fn contains<T: Send + Sync + 'static>(&self) -> bool {
+        self.map.contains_key(&TypeId::of::<T>())
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.map.clear();
+    }
+}
+
+impl PartialEq for Extensions {
+    fn eq(&self, other: &Self) -> bool {
+        self.map.len() == other.map.len()
+            && self.map.iter().all(|(key, entry)| {
+                other
+                    .map
+                    .get(key)
+                    .is_some_and(|other| Arc::ptr_eq(&entry.value, &other.value))
+            })
+    }
+}
+
+impl Eq for Extensions {}
+
+impl fmt::Debug for Extensions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set()
+            .entries(self.map.values().map(|entry| entry.type_name))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_insert_get_remove() {
+        let mut extensions = Extensions::new();
+
+        assert_eq!(extensions.insert(1_u32), None);
+        assert_eq!(extensions.insert(2_u32), Some(Arc::new(1)));
+        extensions.insert("user".to_owned());
+
+        assert_eq!(extensions.len(), 2);
+        assert_eq!(extensions.get::<u32>(), Some(&2));
+        assert_eq!(extensions.get::<String>().unwrap(), "user");
+
+        // values shared with a clone are returned as well
+        let shared = extensions.clone();
+        assert_eq!(shared, extensions);
+        let removed = extensions.remove::<u32>().unwrap();
+        assert_eq!(*removed, 2);
+        assert_eq!(Arc::strong_count(&removed), 2);
+        assert_ne!(shared, extensions);
+        assert_eq!(extensions.remove::<u32>(), None);
+
+        drop(shared);
+        let removed = extensions.remove::<String>().unwrap();
+        assert_eq!(Arc::try_unwrap(removed).unwrap(), "user");
+        assert!(extensions.is_empty());
+    }
+}
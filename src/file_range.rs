@@ -0,0 +1,114 @@
+use std::{
+    fmt,
+    fs::File,
+    io::{self, Write},
+    sync::Arc,
+};
+
+/// a response body referring to `len` bytes of a file starting at `offset`
+///
+/// set with `ResponseBuilder::file_mut`, the bytes are not read into memory but sent straight
+/// from the file by `ResponseBuilder::send`. The file is reference counted, so cloning the
+/// range or the response shares the open file. Two ranges are equal if they refer to the same
+/// part of the very same file.
+///
+/// # Examples
+/// ```no_run
+/// use reqse::{FileRange, ResponseBuilder};
+/// use std::fs::File;
+///
+/// let mut response = ResponseBuilder::ok();
+/// *response.file_mut() = Some(FileRange::from_file(File::open("video.mp4").unwrap()).unwrap());
+/// ```
+#[derive(Clone)]
+pub struct FileRange {
+    file: Arc<File>,
+    offset: u64,
+    len: u64,
+}
+
+impl FileRange {
+    pub fn new(file: File, offset: u64, len: u64) -> Self {
+        Self {
+            file: Arc::new(file),
+            offset,
+            len,
+        }
+    }
+
+    /// the whole file
+    pub fn from_file(file: File) -> io::Result<Self> {
+        let len = file.metadata()?.len();
+        Ok(Self::new(file, 0, len))
+    }
+
+    pub fn file(&self) -> &File {
+        &self.file
+    }
+
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// copies the range into `writer` through a userspace buffer
+    ///
+    /// the file is read at absolute positions, so clones of the range sharing the file can be
+    /// copied from several threads at once. Fails with `UnexpectedEof` if the file is shorter
+    /// than the range.
+    pub(crate) fn copy_to<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<()> {
+        let mut buf = vec![0; self.len.min(64 * 1024) as usize];
+        let mut copied = 0;
+
+        while copied < self.len {
+            let n = buf.len().min((self.len - copied) as usize);
+            match read_at(&self.file, &mut buf[..n], self.offset + copied) {
+                Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+                Ok(n) => {
+                    writer.write_all(&buf[..n])?;
+                    copied += n as u64;
+                }
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => (),
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(unix)]
+fn read_at(file: &File, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+    std::os::unix::fs::FileExt::read_at(file, buf, offset)
+}
+
+#[cfg(windows)]
+fn read_at(file: &File, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+    // `seek_read` moves the cursor of the file, but nothing else relies on it
+    std::os::windows::fs::FileExt::seek_read(file, buf, offset)
+}
+
+impl PartialEq for FileRange {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.file, &other.file) && self.offset == other.offset && self.len == other.len
+    }
+}
+
+impl Eq for FileRange {}
+
+impl fmt::Debug for FileRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FileRange")
+            .field("file", &self.file)
+            .field("offset", &self.offset)
+            .field("len", &self.len)
+            .finish()
+    }
+}
@@ -0,0 +1,194 @@
+use crate::{Error, HeaderMap, Method, Status};
+
+/// how the end of a message body is determined, see RFC 9112 section 6.3
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum BodyLength {
+    /// the body is exactly this many bytes long
+    Fixed(usize),
+    /// the body uses the chunked transfer coding
+    Chunked,
+    /// the body ends when the server closes the connection
+    UntilEof,
+}
+
+/// determines the body length of a response with `status` and `header` to a request with
+/// `request_method`
+pub(crate) fn response_body_length(
+    request_method: Method,
+    status: Status,
+    header: &HeaderMap,
+) -> Result<BodyLength, Error> {
+    // responses to HEAD carry the header of the GET response, including its Content-Length
+    if request_method == Method::Head {
+        return Ok(BodyLength::Fixed(0));
+    }
+
+    if status.is_informational() || matches!(status, Status::NoContent | Status::NotModified) {
+        return Ok(BodyLength::Fixed(0));
+    }
+
+    if let Some(encoding) = header.get("Transfer-Encoding") {
+        let chunked = encoding
+            .rsplit(',')
+            .next()
+            .is_some_and(|last| last.trim().eq_ignore_ascii_case("chunked"));
+
+        return Ok(if chunked {
+            BodyLength::Chunked
+        } else {
+            BodyLength::UntilEof
+        });
+    }
+
+    match header.get("Content-Length") {
+        Some(len) => parse_content_length(len).map(BodyLength::Fixed),
+        None => Ok(BodyLength::UntilEof),
+    }
+}
+
+/// determines the body length of a request with `header`, rejecting every ambiguous framing a
+/// proxy in front of the server might interpret differently, see RFC 9112 section 6.3
+///
+/// - `Transfer-Encoding` and `Content-Length` together
+/// - more than one `Content-Length` field, even with equal values
+/// - a `Content-Length` which is not plain decimal digits
+/// - any `Transfer-Encoding` but a single `chunked`
+pub(crate) fn request_body_length_strict(header: &HeaderMap) -> Result<BodyLength, Error> {
+    let mut content_length = header
+        .iter()
+        .filter(|(key, _)| key.eq_ignore_ascii_case("Content-Length"))
+        .map(|(_, value)| value);
+    let mut transfer_encoding = header
+        .iter()
+        .filter(|(key, _)| key.eq_ignore_ascii_case("Transfer-Encoding"))
+        .map(|(_, value)| value);
+
+    match (
+        content_length.next(),
+        content_length.next(),
+        transfer_encoding.next(),
+        transfer_encoding.next(),
+    ) {
+        (None, _, None, _) => Ok(BodyLength::Fixed(0)),
+        (Some(len), None, None, _) => parse_content_length(len).map(BodyLength::Fixed),
+        (None, _, Some(encoding), None) if encoding.eq_ignore_ascii_case("chunked") => {
+            Ok(BodyLength::Chunked)
+        }
+        _ => Err(Error::InvalidHeader),
+    }
+}
+
+/// whether `name` is a field which frames the body, builders always write their own
+pub(crate) fn is_framing_field(name: &str) -> bool {
+    ["Content-Length", "Transfer-Encoding", "Trailer"]
+        .iter()
+        .any(|field| name.eq_ignore_ascii_case(field))
+}
+
+/// parses a `Content-Length` value, only plain decimal digits are accepted
+pub(crate) fn parse_content_length(len: &str) -> Result<usize, Error> {
+    if len.is_empty() || !len.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(Error::InvalidHeader);
+    }
+
+    len.parse().ok().ok_or(Error::InvalidHeader)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{RequestBuilder, ResponseBuilder};
+
+    #[test]
+    fn test_builders_drop_framing_fields() {
+        let mut response = ResponseBuilder::ok();
+        response.body_mut().extend_from_slice(b"hello");
+        for key in ["content-length", "TRANSFER-ENCODING", "trailer"] {
+            response.header_mut().insert(key.to_owned(), "0".to_owned());
+        }
+        assert_eq!(
+            response.finish(),
+            b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhello"
+        );
+
+        let mut request = RequestBuilder::post("/".to_owned());
+        request.body_but().extend_from_slice(b"hello");
+        request
+            .header_mut()
+            .insert("content-length".to_owned(), "0".to_owned());
+        assert_eq!(
+            request.finish(),
+            b"POST / HTTP/1.1\r\nContent-Length: 5\r\n\r\nhello"
+        );
+    }
+
+    fn body_length(status: Status, header: &str) -> Result<BodyLength, Error> {
+        response_body_length(Method::Get, status, &HeaderMap::new(header).unwrap())
+    }
+
+    #[test]
+    fn test_response_body_length() {
+        assert_eq!(
+            body_length(Status::Ok, "Content-Length: 12\r\n"),
+            Ok(BodyLength::Fixed(12))
+        );
+        assert_eq!(
+            body_length(
+                Status::Ok,
+                "Transfer-Encoding: gzip, chunked\r\nContent-Length: 3\r\n"
+            ),
+            Ok(BodyLength::Chunked)
+        );
+        assert_eq!(
+            body_length(Status::Ok, "Transfer-Encoding: gzip\r\n"),
+            Ok(BodyLength::UntilEof)
+        );
+        assert_eq!(body_length(Status::Ok, ""), Ok(BodyLength::UntilEof));
+        assert_eq!(
+            body_length(Status::NoContent, "Content-Length: 12\r\n"),
+            Ok(BodyLength::Fixed(0))
+        );
+        assert_eq!(
+            body_length(Status::Ok, "Content-Length: +12\r\n"),
+            Err(Error::InvalidHeader)
+        );
+
+        let header = HeaderMap::new("Content-Length: 12\r\n").unwrap();
+        assert_eq!(
+            response_body_length(Method::Head, Status::Ok, &header),
+            Ok(BodyLength::Fixed(0))
+        );
+    }
+
+    #[test]
+    fn test_request_body_length_strict() {
+        let body_length = |header| request_body_length_strict(&HeaderMap::new(header).unwrap());
+
+        assert_eq!(body_length(""), Ok(BodyLength::Fixed(0)));
+        assert_eq!(
+            body_length("Content-Length: 5\r\n"),
+            Ok(BodyLength::Fixed(5))
+        );
+        assert_eq!(
+            body_length("transfer-encoding: Chunked\r\n"),
+            Ok(BodyLength::Chunked)
+        );
+
+        for header in [
+            "Content-Length: 5\r\nContent-Length: 5\r\n",
+            "Content-Length: 5, 5\r\n",
+            "Content-Length: -5\r\n",
+            "Content-Length: 5\r\nTransfer-Encoding: chunked\r\n",
+            "Transfer-Encoding: gzip, chunked\r\n",
+            "Transfer-Encoding: chunked\r\nTransfer-Encoding: chunked\r\n",
+            "Transfer-Encoding: xchunked\r\n",
+        ] {
+            assert_eq!(
+                body_length(header),
+                Err(Error::InvalidHeader),
+                "{:?}",
+                header
+            );
+        }
+    }
+}
@@ -0,0 +1,212 @@
+use std::io;
+
+use crate::{Request, ResponseBuilder};
+
+/// something which answers a request, implemented for plain functions
+/// `fn(Request) -> io::Result<ResponseBuilder>`, `Router` and `Chain`
+pub trait Handler {
+    fn call(&self, request: Request) -> io::Result<ResponseBuilder>;
+
+    /// decides whether the body of a request sent with `Expect: 100-continue` is wanted
+    ///
+    /// `request` holds only the head, its body has not been sent yet. Returning `None` lets the
+    /// server answer `100 Continue` and read the body, returning a response (eg:
+    /// `ResponseBuilder::payload_too_large()`) answers the request without reading the body.
+    /// The default accepts every body.
+    fn check_continue(&self, request: &Request) -> Option<ResponseBuilder> {
+        let _ = request;
+        None
+    }
+}
+
+impl<F> Handler for F
+where
+    F: Fn(Request) -> io::Result<ResponseBuilder>,
+{
+    fn call(&self, request: Request) -> io::Result<ResponseBuilder> {
+        self(request)
+    }
+}
+
+/// code running around a `Handler`, implemented for plain functions
+/// `fn(Request, &dyn Handler) -> io::Result<ResponseBuilder>`
+///
+/// a middleware decides itself whether and when to call `next`: it can inspect or modify the
+/// request before, answer without calling `next` at all (short-circuit) and post-process the
+/// `ResponseBuilder` returned by `next`.
+///
+/// # Examples
+/// ```
+/// use reqse::{Handler, Request, ResponseBuilder, chain};
+/// use std::io;
+///
+/// fn auth(request: Request, next: &dyn Handler) -> io::Result<ResponseBuilder> {
+///     if request.header().get("Authorization") != Some("Bearer secret") {
+///         return Ok(ResponseBuilder::unauthorized());
+///     }
+///     next.call(request)
+/// }
+///
+/// fn server_header(request: Request, next: &dyn Handler) -> io::Result<ResponseBuilder> {
+///     let mut response = next.call(request)?;
+///     response
+///         .header_mut()
+///         .insert("Server".to_owned(), "reqse".to_owned());
+///     Ok(response)
+/// }
+///
+/// fn hello(_: Request) -> io::Result<ResponseBuilder> {
+///     Ok(ResponseBuilder::ok())
+/// }
+///
+/// let handler = chain(server_header, chain(auth, hello));
+///
+/// let request = Request::from_bytes(b"GET / HTTP/1.1\r\n\r\n").unwrap();
+/// let response = handler.call(request).unwrap();
+/// assert_eq!(response.status(), reqse::Status::Unauthorized);
+/// assert_eq!(response.header()["Server"], "reqse");
+/// ```
+pub trait Middleware {
+    fn call(&self, request: Request, next: &dyn Handler) -> io::Result<ResponseBuilder>;
+
+    /// decides whether the body of a request sent with `Expect: 100-continue` is wanted, see
+    /// `Handler::check_continue`
+    ///
+    /// a middleware which rejects requests in `call` (eg: missing authorization) should reject
+    /// them here as well, so their body is not read first. The default asks `next`.
+    fn check_continue(&self, request: &Request, next: &dyn Handler) -> Option<ResponseBuilder> {
+        next.check_continue(request)
+    }
+}
+
+impl<F> Middleware for F
+where
+    F: Fn(Request, &dyn Handler) -> io::Result<ResponseBuilder>,
+{
+    fn call(&self, request: Request, next: &dyn Handler) -> io::Result<ResponseBuilder> {
+        self(request, next)
+    }
+}
+
+/// a `Handler` which runs `middleware` around `next`, see `chain`
+pub struct Chain {
+    middleware: Box<dyn Middleware + Send + Sync>,
+    next: Box<dyn Handler + Send + Sync>,
+}
+
+impl Handler for Chain {
+    fn call(&self, request: Request) -> io::Result<ResponseBuilder> {
+        self.middleware.call(request, self.next.as_ref())
+    }
+
+    fn check_continue(&self, request: &Request) -> Option<ResponseBuilder> {
+        self.middleware.check_continue(request, self.next.as_ref())
+    }
+}
+
+/// wraps `handler` in `middleware`
+///
+/// the result is a `Handler` again, so chains nest: `chain(log, chain(auth, router))` runs `log`
+/// first, then `auth` and finally `router`.
+pub fn chain<M, H>(middleware: M, handler: H) -> Chain
+where
+    M: Middleware + Send + Sync + 'static,
+    H: Handler + Send + Sync + 'static,
+{
+    Chain {
+        middleware: Box::new(middleware),
+        next: Box::new(handler),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Status;
+    use std::sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    };
+
+    fn hello(_: Request) -> io::Result<ResponseBuilder> {
+        let mut response = ResponseBuilder::ok();
+        response.body_mut().extend_from_slice(b"Hello");
+        Ok(response)
+    }
+
+    fn deny_delete(request: Request, next: &dyn Handler) -> io::Result<ResponseBuilder> {
+        match request.method() {
+            crate::Method::Delete => Ok(ResponseBuilder::forbidden()),
+            _ => next.call(request),
+        }
+    }
+
+    fn call(handler: &dyn Handler, raw_request: &str) -> ResponseBuilder {
+        let request = Request::from_bytes(raw_request.as_bytes()).unwrap();
+        handler.call(request).unwrap()
+    }
+
+    #[test]
+    fn test_chain_order_and_short_circuit() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+
+        let count = move |request: Request, next: &dyn Handler| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            let mut response = next.call(request)?;
+            response.body_mut().extend_from_slice(b"!");
+            Ok(response)
+        };
+
+        let handler = chain(count, chain(deny_delete, hello));
+
+        let response = call(&handler, "GET / HTTP/1.1\r\n\r\n");
+        assert_eq!(response.body(), b"Hello!");
+
+        let response = call(&handler, "DELETE / HTTP/1.1\r\n\r\n");
+        assert_eq!(response.status(), Status::Forbidden);
+        assert_eq!(response.body(), b"!");
+
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    struct Auth;
+
+    impl Auth {
+        fn authorized(request: &Request) -> bool {
+            request.header().get("Authorization") == Some("Bearer secret")
+        }
+    }
+
+    impl Middleware for Auth {
+        fn call(&self, request: Request, next: &dyn Handler) -> io::Result<ResponseBuilder> {
+            match Self::authorized(&request) {
+                true => next.call(request),
+                false => Ok(ResponseBuilder::unauthorized()),
+            }
+        }
+
+        fn check_continue(&self, request: &Request, next: &dyn Handler) -> Option<ResponseBuilder> {
+            match Self::authorized(request) {
+                true => next.check_continue(request),
+                false => Some(ResponseBuilder::unauthorized()),
+            }
+        }
+    }
+
+    #[test]
+    fn test_chain_check_continue() {
+        let handler = chain(deny_delete, chain(Auth, hello));
+
+        let request =
+            Request::from_bytes(b"PUT / HTTP/1.1\r\nExpect: 100-continue\r\n\r\n").unwrap();
+        let response = handler.check_continue(&request).unwrap();
+        assert_eq!(response.status(), Status::Unauthorized);
+
+        let request = Request::from_bytes(
+            b"PUT / HTTP/1.1\r\nAuthorization: Bearer secret\r\nExpect: 100-continue\r\n\r\n",
+        )
+        .unwrap();
+        assert!(handler.check_continue(&request).is_none());
+    }
+}
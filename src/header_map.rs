@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use crate::Error;
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
@@ -14,7 +16,7 @@ impl<'a> Iterator for HeaderMapIter<'a> {
         }
         let (line, rest) = self.inner.split_once("\r\n").unwrap_or((self.inner, ""));
         self.inner = rest;
-        line.split_once("\r\n")
+        line.split_once(": ")
     }
 }
 
@@ -39,6 +41,16 @@ impl<'a> HeaderMap<'a> {
         Ok(Self { inner: header })
     }
 
+    /// wraps an already validated header, see `HeaderMap::as_str`
+    pub(crate) fn new_unchecked(header: &'a str) -> Self {
+        Self { inner: header }
+    }
+
+    /// returns the header as it was passed to `HeaderMap::new`, without surrounding whitespace
+    pub(crate) fn as_str(&self) -> &'a str {
+        self.inner
+    }
+
     pub fn iter(&self) -> HeaderMapIter<'a> {
         HeaderMapIter { inner: self.inner }
     }
@@ -47,8 +59,19 @@ impl<'a> HeaderMap<'a> {
         self.iter().count()
     }
 
+    /// returns the value of the first field named `key`, field names are case-insensitive
+    ///
+    /// # Examples
+    /// ```
+    /// use reqse::HeaderMap;
+    ///
+    /// let header = HeaderMap::new("content-length: 5\r\n").unwrap();
+    /// assert_eq!(header.get("Content-Length"), Some("5"));
+    /// ```
     pub fn get(&self, key: &str) -> Option<&'a str> {
-        self.iter().find(|(k, _)| *k == key).map(|(_, v)| v)
+        self.iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v)
     }
 
     pub fn is_empty(&self) -> bool {
@@ -58,4 +81,30 @@ impl<'a> HeaderMap<'a> {
     pub fn contains(&self, key: &str) -> bool {
         self.get(key).is_some()
     }
+
+    /// copies the fields into the map of a builder
+    ///
+    /// repeated fields are combined into one, separated by `, `, as RFC 9110 section 5.3
+    /// allows. `Content-Length` is left out, builders compute it from their body.
+    pub(crate) fn to_builder_header(self) -> HashMap<String, String> {
+        let mut map: HashMap<String, String> = HashMap::new();
+
+        for (key, value) in self.iter() {
+            if key.eq_ignore_ascii_case("Content-Length") {
+                continue;
+            }
+
+            match map.iter_mut().find(|(k, _)| k.eq_ignore_ascii_case(key)) {
+                Some((_, combined)) => {
+                    combined.push_str(", ");
+                    combined.push_str(value);
+                }
+                None => {
+                    map.insert(key.to_owned(), value.to_owned());
+                }
+            }
+        }
+
+        map
+    }
 }
@@ -0,0 +1,72 @@
+use std::{
+    io::{self, Write},
+    net::TcpStream,
+    sync::Arc,
+};
+
+use crate::{ResponseBuilder, Version};
+
+/// sends interim (1xx) responses ahead of the final response
+///
+/// `Server` puts an `Interim` into the extensions of every request. A handler can send any
+/// number of interim responses through it, eg: `103 Early Hints` so the client starts loading
+/// assets while the final response is still computed.
+///
+/// # Examples
+/// ```
+/// use reqse::{Interim, Request, ResponseBuilder};
+/// use std::io;
+///
+/// fn page(request: Request) -> io::Result<ResponseBuilder> {
+///     if let Some(interim) = request.extensions().get::<Interim>() {
+///         let mut hints = ResponseBuilder::early_hints();
+///         hints.header_mut().insert(
+///             "Link".to_owned(),
+///             "</style.css>; rel=preload; as=style".to_owned(),
+///         );
+///         interim.send(&hints)?;
+///     }
+///
+///     let mut response = ResponseBuilder::ok();
+///     response.body_mut().extend_from_slice(b"<link rel=stylesheet href=/style.css>");
+///     Ok(response)
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Interim {
+    stream: Arc<TcpStream>,
+    enabled: bool,
+}
+
+impl Interim {
+    /// interim responses are dropped for HTTP/1.0 requests, those clients do not expect them
+    pub(crate) fn new(stream: Arc<TcpStream>, version: Version) -> Self {
+        Self {
+            stream,
+            enabled: version != Version::Http0,
+        }
+    }
+
+    /// writes the status line and header of `response` to the client
+    ///
+    /// # Error
+    /// - `io::ErrorKind::InvalidInput` if the status of `response` is not an interim one, see
+    ///   `Status::is_interim`
+    /// - `io::ErrorKind::InvalidData` if `response` does not pass `ResponseBuilder::validate`
+    pub fn send(&self, response: &ResponseBuilder) -> io::Result<()> {
+        if !response.status().is_interim() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "not an interim response",
+            ));
+        }
+
+        if !self.enabled {
+            return Ok(());
+        }
+
+        let mut stream = &*self.stream;
+        response.write_to(&mut stream)?;
+        stream.flush()
+    }
+}
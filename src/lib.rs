@@ -1,19 +1,55 @@
+mod chunked;
+mod connection;
+mod date;
 mod error;
+mod extensions;
+mod file_range;
+mod framing;
+mod handler;
 mod header_map;
+mod interim;
 mod method;
+mod owned_request;
+mod owned_response;
+mod params;
 mod request;
 mod request_builder;
 mod response;
 mod response_builder;
+mod router;
+mod sendfile;
+mod server;
 mod status;
+mod syntax;
+mod upgrade;
+mod vectored;
 mod version;
 
+pub mod client;
+pub mod sse;
+pub mod static_files;
+pub mod websocket;
+
+#[cfg(feature = "tokio")]
+pub mod tokio;
+
+pub use connection::should_keep_alive;
 pub use error::Error;
+pub use extensions::Extensions;
+pub use file_range::FileRange;
+pub use handler::{Chain, Handler, Middleware, chain};
 pub use header_map::{HeaderMap, HeaderMapIter};
+pub use interim::Interim;
 pub use method::Method;
+pub use owned_request::OwnedRequest;
+pub use owned_response::OwnedResponse;
+pub use params::Params;
 pub use request::Request;
 pub use request_builder::RequestBuilder;
 pub use response::Response;
 pub use response_builder::ResponseBuilder;
+pub use router::Router;
+pub use server::{Server, ShutdownHandle};
 pub use status::Status;
+pub use upgrade::{Upgrade, Upgraded};
 pub use version::Version;
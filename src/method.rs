@@ -4,9 +4,11 @@ use std::{fmt::Display, str::FromStr};
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum Method {
     Get,
+    Head,
     Post,
     Put,
     Delete,
+    Options,
 }
 
 impl FromStr for Method {
@@ -15,9 +17,11 @@ impl FromStr for Method {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
             "GET" => Ok(Self::Get),
+            "HEAD" => Ok(Self::Head),
             "POST" => Ok(Self::Post),
             "PUT" => Ok(Self::Put),
             "DELETE" => Ok(Self::Delete),
+            "OPTIONS" => Ok(Self::Options),
             _ => Err(Error::InvalidHeader),
         }
     }
@@ -36,9 +40,11 @@ impl Method {
     pub fn to_static_str(&self) -> &'static str {
         match self {
             Method::Get => "GET",
+            Method::Head => "HEAD",
             Method::Post => "POST",
             Method::Put => "PUT",
             Method::Delete => "DELETE",
+            Method::Options => "OPTIONS",
         }
     }
 }
@@ -0,0 +1,70 @@
+use crate::{HeaderMap, Method, Request, Version};
+
+/// a `Request` which owns its data instead of borrowing the buffer it was parsed from
+///
+/// an `OwnedRequest` can be stored, sent to another thread or task and outlives the read buffer,
+/// use `OwnedRequest::as_request` to pass it to code expecting a `Request`
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct OwnedRequest {
+    version: Version,
+    uri: String,
+    method: Method,
+    header: String,
+    body: Vec<u8>,
+}
+
+impl OwnedRequest {
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    pub fn uri(&self) -> &str {
+        &self.uri
+    }
+
+    pub fn method(&self) -> Method {
+        self.method
+    }
+
+    pub fn header(&self) -> HeaderMap<'_> {
+        HeaderMap::new_unchecked(&self.header)
+    }
+
+    pub fn body(&self) -> &[u8] {
+        &self.body
+    }
+
+    /// borrows the owned request as a `Request`
+    ///
+    /// # Examples
+    /// ```
+    /// use reqse::{OwnedRequest, Request};
+    ///
+    /// let buf = b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n".to_vec();
+    /// let owned = OwnedRequest::from(Request::from_bytes(&buf).unwrap());
+    /// drop(buf);
+    ///
+    /// assert_eq!(owned.as_request().header().get("Host"), Some("localhost"));
+    /// ```
+    pub fn as_request(&self) -> Request<'_> {
+        Request::new(
+            self.method,
+            &self.uri,
+            self.version,
+            self.header(),
+            &self.body,
+        )
+    }
+}
+
+impl From<Request<'_>> for OwnedRequest {
+    fn from(request: Request<'_>) -> Self {
+        Self {
+            version: request.version(),
+            uri: request.uri().to_owned(),
+            method: request.method(),
+            header: request.header().as_str().to_owned(),
+            body: request.body().to_vec(),
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use crate::{HeaderMap, Response, Status, Version};
+
+/// a `Response` which owns its data instead of borrowing the buffer it was parsed from
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct OwnedResponse {
+    version: Version,
+    status: Status,
+    header: String,
+    body: Vec<u8>,
+    trailers: String,
+}
+
+impl OwnedResponse {
+    /// `header` and `trailers` have to be valid for `HeaderMap::new`
+    pub(crate) fn new(
+        version: Version,
+        status: Status,
+        header: String,
+        body: Vec<u8>,
+        trailers: String,
+    ) -> Self {
+        Self {
+            version,
+            status,
+            header,
+            body,
+            trailers,
+        }
+    }
+
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    pub fn status(&self) -> Status {
+        self.status
+    }
+
+    pub fn header(&self) -> HeaderMap<'_> {
+        HeaderMap::new_unchecked(&self.header)
+    }
+
+    pub fn body(&self) -> &[u8] {
+        &self.body
+    }
+
+    /// the trailer fields sent after a chunked body, empty for other bodies
+    ///
+    /// fields which are not allowed as trailers, like `Content-Length`, are dropped.
+    pub fn trailers(&self) -> HeaderMap<'_> {
+        HeaderMap::new_unchecked(&self.trailers)
+    }
+
+    /// borrows the owned response as a `Response`
+    pub fn as_response(&self) -> Response<'_> {
+        Response::new(self.version, self.status, self.header(), &self.body)
+    }
+}
+
+impl From<Response<'_>> for OwnedResponse {
+    fn from(response: Response<'_>) -> Self {
+        Self {
+            version: response.version(),
+            status: response.status(),
+            header: response.header().as_str().to_owned(),
+            body: response.body().to_vec(),
+            trailers: String::new(),
+        }
+    }
+}
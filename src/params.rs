@@ -0,0 +1,36 @@
+/// path parameters captured by a `Router` pattern (eg: `id` for `/users/:id`)
+///
+/// the values borrow the request uri and are not percent-decoded
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Params<'a> {
+    inner: Vec<(String, &'a str)>,
+}
+
+impl<'a> Params<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, name: String, value: &'a str) {
+        match self.inner.iter_mut().find(|(k, _)| *k == name) {
+            Some((_, v)) => *v = value,
+            None => self.inner.push((name, value)),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&'a str> {
+        self.inner.iter().find(|(k, _)| k == name).map(|(_, v)| *v)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &'a str)> {
+        self.inner.iter().map(|(k, v)| (k.as_str(), *v))
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+}
@@ -1,4 +1,9 @@
-use crate::{Error, HeaderMap, Method, Version};
+use crate::{
+    Error, Extensions, HeaderMap, Method, OwnedRequest, Params, RequestBuilder, Version, chunked,
+    connection,
+    framing::{self, BodyLength},
+    syntax,
+};
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct Request<'a> {
@@ -7,9 +12,31 @@ pub struct Request<'a> {
     method: Method,
     header: HeaderMap<'a>,
     body: &'a [u8],
+    trailers: HeaderMap<'a>,
+    params: Params<'a>,
+    extensions: Extensions,
 }
 
 impl<'a> Request<'a> {
+    pub(crate) fn new(
+        method: Method,
+        uri: &'a str,
+        version: Version,
+        header: HeaderMap<'a>,
+        body: &'a [u8],
+    ) -> Self {
+        Self {
+            version,
+            uri,
+            method,
+            header,
+            body,
+            trailers: HeaderMap::new_unchecked(""),
+            params: Params::new(),
+            extensions: Extensions::new(),
+        }
+    }
+
     pub fn version(&self) -> Version {
         self.version
     }
@@ -30,6 +57,114 @@ impl<'a> Request<'a> {
         self.body
     }
 
+    /// the trailer fields sent after a chunked body
+    ///
+    /// only requests handed out by the `Server` have their chunked body decoded, the trailers
+    /// of other requests are empty
+    pub fn trailers(&self) -> &HeaderMap<'a> {
+        &self.trailers
+    }
+
+    /// returns the path of the uri, without query and fragment
+    ///
+    /// # Examples
+    /// ```
+    /// use reqse::Request;
+    ///
+    /// let request = Request::from_bytes(b"GET /users?page=2 HTTP/1.1\r\n\r\n").unwrap();
+    /// assert_eq!(request.path(), "/users");
+    /// ```
+    pub fn path(&self) -> &'a str {
+        self.uri.split(['?', '#']).next().unwrap_or(self.uri)
+    }
+
+    /// path parameters captured by the `Router` which dispatched this request
+    pub fn params(&self) -> &Params<'a> {
+        &self.params
+    }
+
+    pub fn params_mut(&mut self) -> &mut Params<'a> {
+        &mut self.params
+    }
+
+    /// shorthand for `request.params().get(name)`
+    pub fn param(&self, name: &str) -> Option<&'a str> {
+        self.params.get(name)
+    }
+
+    /// typed values attached to the request, usually by a `Middleware` for the handlers after it
+    ///
+    /// # Examples
+    /// ```
+    /// use reqse::{Handler, Request, ResponseBuilder, chain};
+    /// use std::io;
+    ///
+    /// struct UserId(u64);
+    ///
+    /// fn auth(mut request: Request, next: &dyn Handler) -> io::Result<ResponseBuilder> {
+    ///     match request.header().get("Authorization") {
+    ///         Some("Bearer secret") => request.extensions_mut().insert(UserId(42)),
+    ///         _ => return Ok(ResponseBuilder::unauthorized()),
+    ///     };
+    ///     next.call(request)
+    /// }
+    ///
+    /// fn me(request: Request) -> io::Result<ResponseBuilder> {
+    ///     let UserId(id) = request.extensions().get::<UserId>().unwrap();
+    ///     let mut response = ResponseBuilder::ok();
+    ///     response.body_mut().extend_from_slice(id.to_string().as_bytes());
+    ///     Ok(response)
+    /// }
+    ///
+    /// let handler = chain(auth, me);
+    /// let raw_request = b"GET /me HTTP/1.1\r\nAuthorization: Bearer secret\r\n\r\n";
+    /// let response = handler.call(Request::from_bytes(raw_request).unwrap()).unwrap();
+    /// assert_eq!(response.body(), b"42");
+    /// ```
+    pub fn extensions(&self) -> &Extensions {
+        &self.extensions
+    }
+
+    pub fn extensions_mut(&mut self) -> &mut Extensions {
+        &mut self.extensions
+    }
+
+    /// copies the request out of the buffer it was parsed from
+    ///
+    /// path parameters and extensions are not copied, they belong to the routing of this request
+    pub fn into_owned(self) -> OwnedRequest {
+        OwnedRequest::from(self)
+    }
+
+    /// creates a `RequestBuilder` with the method, uri, version, header and body of this request
+    ///
+    /// repeated header fields are combined into one field and `Content-Length` is left to the
+    /// builder, so a proxy can modify the body before forwarding the request
+    ///
+    /// # Examples
+    /// ```
+    /// use reqse::Request;
+    ///
+    /// let raw_request = b"POST /echo HTTP/1.1\r\nHost: a\r\nContent-Length: 4\r\n\r\nping";
+    /// let request = Request::from_bytes(raw_request).unwrap();
+    ///
+    /// let mut builder = request.to_builder();
+    /// builder.header_mut().insert("Host".to_owned(), "b".to_owned());
+    /// builder.body_but().extend_from_slice(b"!");
+    ///
+    /// assert_eq!(
+    ///     builder.finish(),
+    ///     b"POST /echo HTTP/1.1\r\nHost: b\r\nContent-Length: 5\r\n\r\nping!"
+    /// );
+    /// ```
+    pub fn to_builder(&self) -> RequestBuilder {
+        let mut builder = RequestBuilder::new(self.method, self.uri.to_owned());
+        *builder.version_mut() = self.version;
+        *builder.header_mut() = self.header.to_builder_header();
+        builder.body_but().extend_from_slice(self.body);
+        builder
+    }
+
     /// creates a request from bytes
     ///
     /// this function is usefull for converting a byte buffer received from a TcpStream into an
@@ -53,6 +188,227 @@ impl<'a> Request<'a> {
     /// assert!(request.body().is_empty());
     /// ```
     pub fn from_bytes(buf: &'a [u8]) -> Result<Self, Error> {
+        Self::parse(buf).map(|(request, _)| request)
+    }
+
+    /// creates a request from the request line and header at the start of `buf`, without
+    /// waiting for the body
+    ///
+    /// the returned request has an empty body. This lets a server look at a request before its
+    /// body arrived, eg: to answer `Expect: 100-continue` (see `Request::expects_continue`) or
+    /// to reject an upload which is too large.
+    ///
+    /// # Error
+    /// see `Request::from_bytes`, `Error::NotEnoughData` is only returned if the head is not
+    /// complete
+    ///
+    /// # Example
+    /// ```
+    /// use reqse::Request;
+    ///
+    /// let raw_request =
+    ///     b"PUT /upload HTTP/1.1\r\nContent-Length: 1000000\r\nExpect: 100-continue\r\n\r\n";
+    ///
+    /// assert!(Request::from_bytes(raw_request).is_err());
+    ///
+    /// let request = Request::from_bytes_head(raw_request).unwrap();
+    /// assert!(request.expects_continue());
+    /// assert_eq!(request.header().get("Content-Length"), Some("1000000"));
+    /// ```
+    pub fn from_bytes_head(buf: &'a [u8]) -> Result<Self, Error> {
+        Self::parse_head(buf).map(|(request, _)| request)
+    }
+
+    /// whether the client waits for an interim `100 Continue` before sending the body
+    ///
+    /// the server either sends `100 Continue` (see `Status::Continue`) and reads the body, or
+    /// answers right away with a final status like `413 Payload Too Large` or
+    /// `417 Expectation Failed`. `Expect` is ignored for HTTP/1.0 requests, see RFC 9110
+    /// section 10.1.1.
+    pub fn expects_continue(&self) -> bool {
+        self.version != Version::Http0
+            && self
+                .header
+                .get("Expect")
+                .is_some_and(|expect| expect.trim().eq_ignore_ascii_case("100-continue"))
+    }
+
+    /// the protocols the client offers to switch to, that is the `Upgrade` field if
+    /// `Connection` lists the `upgrade` option
+    ///
+    /// a handler accepts one of them by answering with `ResponseBuilder::switching_protocols`
+    /// and taking over the connection, see `Upgrade`. HTTP/1.0 requests can not be upgraded,
+    /// see RFC 9110 section 7.8.
+    ///
+    /// # Examples
+    /// ```
+    /// use reqse::Request;
+    ///
+    /// let raw_request =
+    ///     b"GET /chat HTTP/1.1\r\nConnection: Upgrade\r\nUpgrade: websocket\r\n\r\n";
+    /// let request = Request::from_bytes(raw_request).unwrap();
+    ///
+    /// assert_eq!(request.upgrade(), Some("websocket"));
+    /// ```
+    pub fn upgrade(&self) -> Option<&'a str> {
+        if self.version == Version::Http0 || !connection::has_option(self.header.iter(), "upgrade")
+        {
+            return None;
+        }
+
+        self.header.get("Upgrade")
+    }
+
+    /// creates a request from bytes, rejecting everything which could be framed differently by
+    /// another HTTP implementation
+    ///
+    /// a server behind a proxy has to agree with the proxy where a request ends, otherwise an
+    /// attacker can smuggle a second request inside the body of the first one. On top of
+    /// `Request::from_bytes` this rejects with `Error::InvalidHeader`:
+    /// - `Content-Length` together with `Transfer-Encoding`
+    /// - repeated `Content-Length` fields and values which are not plain digits (`+5`, ` 5`,
+    ///   `5, 5`)
+    /// - any `Transfer-Encoding` but `chunked`
+    /// - whitespace between field name and colon and other invalid field names
+    /// - control characters in field values
+    /// - CR or LF not being part of a CRLF
+    /// - obsolete line folding (a field line starting with whitespace)
+    /// - a request line not made of exactly three parts separated by single spaces
+    ///
+    /// a body with `Transfer-Encoding: chunked` is framed by its chunks, `Request::body` returns
+    /// it still chunk-encoded. The `Server` decodes it before calling the handler.
+    ///
+    /// # Example
+    /// ```
+    /// use reqse::{Error, Request};
+    ///
+    /// let raw_request =
+    ///     b"POST / HTTP/1.1\r\nContent-Length: 4\r\nTransfer-Encoding: chunked\r\n\r\n0\r\n\r\n";
+    ///
+    /// assert!(Request::from_bytes(raw_request).is_ok());
+    /// assert_eq!(Request::from_bytes_strict(raw_request), Err(Error::InvalidHeader));
+    /// ```
+    pub fn from_bytes_strict(buf: &'a [u8]) -> Result<Self, Error> {
+        Self::parse_strict(buf).map(|(request, _)| request)
+    }
+
+    /// strict counterpart of `Request::parse`, see `Request::from_bytes_strict`
+    pub(crate) fn parse_strict(buf: &'a [u8]) -> Result<(Self, usize), Error> {
+        let (request, mid) = Self::parse_head_strict(buf)?;
+
+        let len = match framing::request_body_length_strict(request.header())? {
+            BodyLength::Fixed(len) if buf.len() - mid < len => return Err(Error::NotEnoughData),
+            BodyLength::Fixed(len) => len,
+            BodyLength::Chunked => chunked::decode(&buf[mid..])?.2,
+            BodyLength::UntilEof => return Err(Error::InvalidHeader),
+        };
+
+        let request = Request {
+            body: &buf[mid..mid + len],
+            ..request
+        };
+
+        Ok((request, mid + len))
+    }
+
+    /// decodes a body sent with `Transfer-Encoding: chunked`, returns the decoded body and the
+    /// trailer fields or `None` if the body is not chunked
+    pub(crate) fn decode_chunked(&self) -> Result<Option<(Vec<u8>, String)>, Error> {
+        match framing::request_body_length_strict(&self.header)? {
+            BodyLength::Chunked => {
+                let (body, trailers, _) = chunked::decode(self.body)?;
+                Ok(Some((body, trailers)))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// replaces the body, `trailers` has to be valid for `HeaderMap::new`
+    pub(crate) fn with_body<'b>(self, body: &'b [u8], trailers: &'b str) -> Request<'b>
+    where
+        'a: 'b,
+    {
+        Request {
+            body,
+            trailers: HeaderMap::new_unchecked(trailers),
+            ..self
+        }
+    }
+
+    /// strictly parses the request line and header at the start of `buf`, see
+    /// `Request::from_bytes_strict`
+    ///
+    /// returns the request with an empty body and the length of the head
+    pub(crate) fn parse_head_strict(buf: &'a [u8]) -> Result<(Self, usize), Error> {
+        let mid = buf.windows(4).position(|w| w == b"\r\n\r\n").map(|i| i + 4);
+
+        // reject as early as possible, a bare LF may also hide the end of the head
+        if syntax::has_bare_cr_or_lf(&buf[..mid.unwrap_or(buf.len())]) {
+            return Err(Error::InvalidHeader);
+        }
+
+        let mid = mid.ok_or(Error::NotEnoughData)?;
+        let head = std::str::from_utf8(&buf[..mid])
+            .ok()
+            .ok_or(Error::InvalidUtf8)?;
+
+        let (request_line, fields) = head.split_once("\r\n").ok_or(Error::InvalidHeader)?;
+        let fields = &fields[..fields.len() - 2];
+
+        let mut request_line = request_line.split(' ');
+
+        let method: Method = request_line.next().ok_or(Error::InvalidHeader)?.parse()?;
+        let uri = request_line.next().ok_or(Error::InvalidHeader)?;
+        let version: Version = request_line.next().ok_or(Error::InvalidHeader)?.parse()?;
+
+        if uri.is_empty() || request_line.next().is_some() {
+            return Err(Error::InvalidHeader);
+        }
+
+        for line in fields.split_terminator("\r\n") {
+            let (name, value) = line.split_once(':').ok_or(Error::InvalidHeader)?;
+
+            // an obs-fold line starts with whitespace and so has no valid name either
+            if !syntax::is_token(name) || !value.bytes().all(syntax::is_field_vchar) {
+                return Err(Error::InvalidHeader);
+            }
+        }
+
+        let header = HeaderMap::new(fields)?;
+
+        Ok((Request::new(method, uri, version, header, &[]), mid))
+    }
+
+    /// parses a request from the start of `buf` and also returns the number of bytes the request
+    /// occupies in `buf`, anything after that belongs to the next request on the connection
+    pub(crate) fn parse(buf: &'a [u8]) -> Result<(Self, usize), Error> {
+        let (request, mid) = Self::parse_head(buf)?;
+        let body = &buf[mid..];
+
+        let content_len: usize = request
+            .header
+            .get("Content-Length")
+            .unwrap_or("0")
+            .parse()
+            .ok()
+            .ok_or(Error::InvalidHeader)?;
+
+        if body.len() < content_len {
+            return Err(Error::NotEnoughData);
+        }
+
+        let request = Request {
+            body: &body[..content_len],
+            ..request
+        };
+
+        Ok((request, mid + content_len))
+    }
+
+    /// parses the request line and header at the start of `buf`
+    ///
+    /// returns the request with an empty body and the length of the head
+    fn parse_head(buf: &'a [u8]) -> Result<(Self, usize), Error> {
         let mid = buf
             .windows(4)
             .enumerate()
@@ -61,7 +417,6 @@ impl<'a> Request<'a> {
             .ok_or(Error::NotEnoughData)?;
 
         let header = &buf[..mid];
-        let body = &buf[mid..];
 
         let header = std::str::from_utf8(header).ok().ok_or(Error::InvalidUtf8)?;
 
@@ -79,26 +434,7 @@ impl<'a> Request<'a> {
 
         let header = HeaderMap::new(header)?;
 
-        let content_len: usize = header
-            .get("Content-Length")
-            .unwrap_or("0")
-            .parse()
-            .ok()
-            .ok_or(Error::InvalidHeader)?;
-
-        if body.len() < content_len {
-            return Err(Error::NotEnoughData);
-        }
-
-        let body = &body[..content_len];
-
-        Ok(Request {
-            version,
-            uri,
-            method,
-            header,
-            body,
-        })
+        Ok((Request::new(method, uri, version, header, &[]), mid))
     }
 }
 
@@ -116,4 +452,112 @@ mod test {
         assert_eq!(request.version(), Version::Http1);
         assert!(request.body().is_empty());
     }
+
+    #[test]
+    fn test_from_bytes_body() {
+        let raw_request = b"POST /echo HTTP/1.1\r\nContent-Length: 5\r\n\r\nhelloGET";
+        let (request, len) = Request::parse(raw_request).unwrap();
+
+        assert_eq!(request.method(), Method::Post);
+        assert_eq!(request.header().get("Content-Length"), Some("5"));
+        assert_eq!(request.body(), b"hello");
+        assert_eq!(&raw_request[len..], b"GET");
+    }
+
+    #[test]
+    fn test_into_owned_and_to_builder() {
+        let raw_request =
+            b"PUT /a HTTP/1.0\r\nAccept: a\r\naccept: b\r\ncontent-length: 2\r\n\r\nhi".to_vec();
+        let request = Request::from_bytes(&raw_request).unwrap();
+        let builder = request.to_builder();
+        let owned = request.into_owned();
+        drop(raw_request);
+
+        assert_eq!(owned.as_request().body(), b"hi");
+        assert_eq!(owned.as_request().to_builder(), builder);
+
+        assert_eq!(builder.method(), Method::Put);
+        assert_eq!(builder.version(), Version::Http0);
+        assert_eq!(builder.header().len(), 1);
+        assert_eq!(builder.header()["Accept"], "a, b");
+        assert_eq!(
+            builder.finish(),
+            b"PUT /a HTTP/1.0\r\nAccept: a, b\r\nContent-Length: 2\r\n\r\nhi"
+        );
+    }
+
+    /// payloads from published request smuggling research, each is framed differently by at
+    /// least two popular HTTP implementations
+    const SMUGGLING_CORPUS: &[&[u8]] = &[
+        // CL.TE and TE.CL
+        b"POST / HTTP/1.1\r\nContent-Length: 13\r\nTransfer-Encoding: chunked\r\n\r\n0\r\n\r\nSMUGGLED",
+        b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\nContent-Length: 3\r\n\r\n8\r\nSMUGGLED\r\n0\r\n\r\n",
+        // duplicate and differing Content-Length
+        b"POST / HTTP/1.1\r\nContent-Length: 0\r\nContent-Length: 5\r\n\r\nGET /",
+        b"POST / HTTP/1.1\r\nContent-Length: 5\r\ncontent-length: 5\r\n\r\nhello",
+        b"POST / HTTP/1.1\r\nContent-Length: 5, 5\r\n\r\nhello",
+        // signed, padded and non-decimal Content-Length
+        b"POST / HTTP/1.1\r\nContent-Length: +5\r\n\r\nhello",
+        b"POST / HTTP/1.1\r\nContent-Length: -1\r\n\r\n",
+        b"POST / HTTP/1.1\r\nContent-Length:  5\r\n\r\nhello",
+        b"POST / HTTP/1.1\r\nContent-Length: 0x5\r\n\r\nhello",
+        // whitespace before the colon
+        b"POST / HTTP/1.1\r\nContent-Length : 5\r\n\r\nhello",
+        b"POST / HTTP/1.1\r\nTransfer-Encoding\t: chunked\r\n\r\n0\r\n\r\n",
+        // obfuscated Transfer-Encoding
+        b"POST / HTTP/1.1\r\nTransfer-Encoding: xchunked\r\n\r\n0\r\n\r\n",
+        b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked, identity\r\n\r\n0\r\n\r\n",
+        b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\nTransfer-encoding: cow\r\n\r\n0\r\n\r\n",
+        b"POST / HTTP/1.1\r\nTransfer-Encoding: \x0bchunked\r\n\r\n0\r\n\r\n",
+        // bare LF and CR
+        b"POST / HTTP/1.1\nContent-Length: 5\r\n\r\nhello",
+        b"POST / HTTP/1.1\r\nContent-Length: 5\nX: y\r\n\r\nhello",
+        b"POST / HTTP/1.1\r\nX: y\rContent-Length: 5\r\n\r\nhello",
+        b"GET / HTTP/1.1\r\nX: y\n\nGET /admin HTTP/1.1\r\n\r\n",
+        // obs-fold
+        b"POST / HTTP/1.1\r\nX: y\r\n Content-Length: 5\r\n\r\nhello",
+        b"POST / HTTP/1.1\r\nTransfer-Encoding: identity\r\n\tchunked\r\n\r\n0\r\n\r\n",
+        b"POST / HTTP/1.1\r\n Content-Length: 5\r\n\r\nhello",
+        // malformed request line
+        b"GET  / HTTP/1.1\r\n\r\n",
+        b"GET / HTTP/1.1 extra\r\n\r\n",
+        b"GET\t/ HTTP/1.1\r\n\r\n",
+        // a chunk size which overflows the end of the chunk data
+        b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\nffffffffffffffed\r\nab",
+    ];
+
+    #[test]
+    fn test_from_bytes_strict_rejects_smuggling_corpus() {
+        for raw_request in SMUGGLING_CORPUS {
+            assert_eq!(
+                Request::from_bytes_strict(raw_request),
+                Err(Error::InvalidHeader),
+                "{}",
+                String::from_utf8_lossy(raw_request)
+            );
+        }
+    }
+
+    #[test]
+    fn test_from_bytes_strict() {
+        let raw_request = b"POST /echo HTTP/1.1\r\nContent-Length: 5\r\n\r\nhelloGET";
+        let (request, len) = Request::parse_strict(raw_request).unwrap();
+        assert_eq!(request.body(), b"hello");
+        assert_eq!(len, raw_request.len() - 3);
+
+        let raw_request =
+            b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n0\r\n\r\nGET";
+        let (request, len) = Request::parse_strict(raw_request).unwrap();
+        assert_eq!(request.body(), b"5\r\nhello\r\n0\r\n\r\n");
+        assert_eq!(&raw_request[len..], b"GET");
+
+        assert_eq!(
+            Request::from_bytes_strict(b"GET / HTTP/1.1\r\nHost: a\r"),
+            Err(Error::NotEnoughData)
+        );
+        assert_eq!(
+            Request::from_bytes_strict(b"GET / HTTP/1.1\r\nHost: a\n"),
+            Err(Error::InvalidHeader)
+        );
+    }
 }
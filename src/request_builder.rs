@@ -1,6 +1,11 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    io::{self, IoSlice, Write},
+};
 
-use crate::{Method, Version};
+use crate::{
+    Error, Method, Version, chunked, connection, framing, sse, syntax, vectored, websocket,
+};
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct RequestBuilder {
@@ -9,6 +14,7 @@ pub struct RequestBuilder {
     version: Version,
     header: HashMap<String, String>,
     body: Vec<u8>,
+    trailers: HashMap<String, String>,
 }
 
 impl RequestBuilder {
@@ -19,6 +25,7 @@ impl RequestBuilder {
             version: Version::default(),
             header: HashMap::default(),
             body: Vec::default(),
+            trailers: HashMap::default(),
         }
     }
 
@@ -26,6 +33,10 @@ impl RequestBuilder {
         Self::new(Method::Get, uri)
     }
 
+    pub fn head(uri: String) -> Self {
+        Self::new(Method::Head, uri)
+    }
+
     pub fn post(uri: String) -> Self {
         Self::new(Method::Post, uri)
     }
@@ -38,6 +49,39 @@ impl RequestBuilder {
         Self::new(Method::Delete, uri)
     }
 
+    pub fn options(uri: String) -> Self {
+        Self::new(Method::Options, uri)
+    }
+
+    /// a `GET` request for a `text/event-stream`, see `Client::event_stream`
+    pub fn event_stream(uri: String) -> Self {
+        let mut request = Self::get(uri);
+        request
+            .header
+            .insert("Accept".to_owned(), sse::CONTENT_TYPE.to_owned());
+        request
+            .header
+            .insert("Cache-Control".to_owned(), "no-cache".to_owned());
+        request
+    }
+
+    /// a `GET` request opening a WebSocket connection with a random `Sec-WebSocket-Key`
+    ///
+    /// subprotocols are offered by adding a `Sec-WebSocket-Protocol` field, see
+    /// `websocket::connect` and `websocket::validate_response`
+    pub fn websocket(uri: String) -> Self {
+        let mut request = Self::get(uri);
+        for (key, value) in [
+            ("Connection", "Upgrade".to_owned()),
+            ("Upgrade", "websocket".to_owned()),
+            ("Sec-WebSocket-Version", "13".to_owned()),
+            ("Sec-WebSocket-Key", websocket::generate_key()),
+        ] {
+            request.header.insert(key.to_owned(), value);
+        }
+        request
+    }
+
     pub fn uri(&self) -> &str {
         &self.uri
     }
@@ -78,38 +122,194 @@ impl RequestBuilder {
         &mut self.body
     }
 
-    pub fn finish(mut self) -> Vec<u8> {
-        if self.body.is_empty() {
-            self.header.remove("Content-Length");
+    /// trailer fields sent after the body
+    ///
+    /// a request with trailers is sent with `Transfer-Encoding: chunked` and announces the
+    /// trailer names in a `Trailer` header, see `ResponseBuilder::trailers_mut`
+    pub fn trailers(&self) -> &HashMap<String, String> {
+        &self.trailers
+    }
+
+    pub fn trailers_mut(&mut self) -> &mut HashMap<String, String> {
+        &mut self.trailers
+    }
+
+    /// whether the body is sent with the chunked transfer coding, which is needed for trailers
+    ///
+    /// HTTP/1.0 does not know the chunked transfer coding, trailers are dropped there
+    fn chunked(&self) -> bool {
+        !self.trailers.is_empty() && self.version != Version::Http0
+    }
+
+    /// the fields of `header` which are written, framing fields in any casing are left to
+    /// `framing_headers` and hop-by-hop fields named in `Connection` are dropped
+    fn headers(&self) -> impl Iterator<Item = (&str, &str)> {
+        let nominated = connection::nominated_fields(&self.header);
+
+        self.header
+            .iter()
+            .filter(move |(key, _)| {
+                !framing::is_framing_field(key) && !connection::is_nominated(&nominated, key)
+            })
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+
+    /// the framing fields written instead of the ones in `header`
+    fn framing_headers(&self) -> Vec<(&'static str, String)> {
+        if self.chunked() {
+            vec![
+                ("Transfer-Encoding", "chunked".to_owned()),
+                ("Trailer", chunked::trailer_header(&self.trailers)),
+            ]
+        } else if !self.body.is_empty() {
+            vec![("Content-Length", self.body.len().to_string())]
         } else {
-            self.header
-                .insert("Content-Length".to_owned(), self.body.len().to_string());
+            Vec::new()
         }
+    }
+
+    /// the bytes written before and after the body, they frame it as a chunk if needed
+    fn body_frame(&self) -> (Vec<u8>, Vec<u8>) {
+        if self.chunked() {
+            chunked::frame(self.body.len() as u64, &self.trailers)
+        } else {
+            (Vec::new(), Vec::new())
+        }
+    }
 
-        let mut buf = vec![];
+    /// serializes the request line and the header block including the empty line ending it
+    fn serialize_head(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(self.head_len());
 
         buf.extend_from_slice(self.method.to_static_str().as_bytes());
         buf.push(b' ');
-
-        let mut uri = self.uri.into_bytes();
-        buf.append(&mut uri);
-
+        buf.extend_from_slice(self.uri.as_bytes());
         buf.push(b' ');
-
         buf.extend_from_slice(self.version.to_static().as_bytes());
 
-        for (key, value) in self.header.into_iter() {
-            let mut key = key.into_bytes();
-            let mut value = value.into_bytes();
-            buf.extend_from_slice(b"\r\n");
-            buf.append(&mut key);
-            buf.extend_from_slice(b": ");
-            buf.append(&mut value);
+        for (key, value) in self.headers() {
+            vectored::push_header_line(&mut buf, key, value);
+        }
+
+        for (key, value) in self.framing_headers() {
+            vectored::push_header_line(&mut buf, key, &value);
         }
 
         buf.extend_from_slice(b"\r\n\r\n");
-        buf.append(&mut self.body);
 
         buf
     }
+
+    fn head_len(&self) -> usize {
+        let request_line = self.method.to_static_str().len()
+            + 1
+            + self.uri.len()
+            + 1
+            + self.version.to_static().len();
+        let headers = self
+            .headers()
+            .map(|(key, value)| vectored::header_line_len(key, value))
+            .sum::<usize>();
+        let framing_headers = self
+            .framing_headers()
+            .iter()
+            .map(|(key, value)| vectored::header_line_len(key, value))
+            .sum::<usize>();
+
+        request_line + headers + framing_headers + 4
+    }
+
+    /// number of bytes `write_to` writes
+    pub fn serialized_len(&self) -> usize {
+        let (prefix, suffix) = self.body_frame();
+        self.head_len() + prefix.len() + self.body.len() + suffix.len()
+    }
+
+    /// checks that the uri and the header can be sent without changing the meaning of the
+    /// message
+    ///
+    /// the uri must be non-empty visible ASCII (anything else has to be percent-encoded), field
+    /// names have to be tokens and field values must not contain control characters like CR or
+    /// LF
+    ///
+    /// # Error
+    /// - `Error::InvalidUri` if the uri is invalid
+    /// - `Error::InvalidHeader` if a field name or value is invalid
+    ///
+    /// # Examples
+    /// ```
+    /// use reqse::{Error, RequestBuilder};
+    ///
+    /// let request = RequestBuilder::get("/ HTTP/1.1\r\nHost: evil\r\n\r\nGET /".into());
+    /// assert_eq!(request.validate(), Err(Error::InvalidUri));
+    /// assert_eq!(request.try_finish(), Err(Error::InvalidUri));
+    /// ```
+    pub fn validate(&self) -> Result<(), Error> {
+        if !syntax::is_request_target(&self.uri) {
+            return Err(Error::InvalidUri);
+        }
+
+        syntax::validate_header(&self.header)?;
+        chunked::validate_trailers(&self.trailers)
+    }
+
+    /// writes the serialized request to `writer`
+    ///
+    /// the request is validated first, see `validate`. The head and the body are handed to
+    /// `writer` with a single `write_vectored`, so the body is never copied into an
+    /// intermediate buffer
+    ///
+    /// # Examples
+    /// ```
+    /// use reqse::RequestBuilder;
+    ///
+    /// let mut request = RequestBuilder::post("/echo".into());
+    /// request.body_but().extend_from_slice(b"ping");
+    ///
+    /// let mut buf = Vec::new();
+    /// request.write_to(&mut buf).unwrap();
+    ///
+    /// assert_eq!(buf, b"POST /echo HTTP/1.1\r\nContent-Length: 4\r\n\r\nping");
+    /// assert_eq!(buf.len(), request.serialized_len());
+    /// ```
+    pub fn write_to<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<()> {
+        self.validate()?;
+        self.write_unchecked(writer)
+    }
+
+    fn write_unchecked<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<()> {
+        let head = self.serialize_head();
+        let (prefix, suffix) = self.body_frame();
+
+        vectored::write_all_vectored(
+            writer,
+            &mut [
+                IoSlice::new(&head),
+                IoSlice::new(&prefix),
+                IoSlice::new(&self.body),
+                IoSlice::new(&suffix),
+            ],
+        )
+    }
+
+    /// serializes the request after validating it, see `validate`
+    ///
+    /// # Error
+    /// see `RequestBuilder::validate`
+    pub fn try_finish(self) -> Result<Vec<u8>, Error> {
+        self.validate()?;
+        Ok(self.finish())
+    }
+
+    /// serializes the request without validating it, see `try_finish`
+    pub fn finish(self) -> Vec<u8> {
+        let (prefix, suffix) = self.body_frame();
+
+        let mut buf = Vec::with_capacity(self.serialized_len());
+        buf.extend_from_slice(&self.serialize_head());
+        buf.extend_from_slice(&prefix);
+        buf.extend_from_slice(&self.body);
+        buf.extend_from_slice(&suffix);
+        buf
+    }
 }
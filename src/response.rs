@@ -1,4 +1,7 @@
-use crate::{Error, HeaderMap, Status, Version};
+use crate::{
+    Error, HeaderMap, Method, OwnedResponse, ResponseBuilder, Status, Version, chunked,
+    framing::{self, BodyLength},
+};
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct Response<'a> {
@@ -9,6 +12,20 @@ pub struct Response<'a> {
 }
 
 impl<'a> Response<'a> {
+    pub(crate) fn new(
+        version: Version,
+        status: Status,
+        header: HeaderMap<'a>,
+        body: &'a [u8],
+    ) -> Self {
+        Self {
+            version,
+            status,
+            header,
+            body,
+        }
+    }
+
     pub fn version(&self) -> Version {
         self.version
     }
@@ -25,30 +42,195 @@ impl<'a> Response<'a> {
         self.body
     }
 
+    /// copies the response out of the buffer it was parsed from
+    pub fn into_owned(self) -> OwnedResponse {
+        OwnedResponse::from(self)
+    }
+
+    /// creates a `ResponseBuilder` with the version, status, header and body of this response
+    ///
+    /// repeated header fields are combined into one field and `Content-Length` is left to the
+    /// builder
+    ///
+    /// # Examples
+    /// ```
+    /// use reqse::{Response, Status};
+    ///
+    /// let raw_response = b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhello";
+    /// let response = Response::from_bytes(raw_response).unwrap();
+    ///
+    /// let mut builder = response.to_builder();
+    /// *builder.status_mut() = Status::Created;
+    ///
+    /// assert_eq!(
+    ///     builder.finish(),
+    ///     b"HTTP/1.1 201 Created\r\nContent-Length: 5\r\n\r\nhello"
+    /// );
+    /// ```
+    pub fn to_builder(&self) -> ResponseBuilder {
+        let mut builder = ResponseBuilder::new(self.status);
+        *builder.version_mut() = self.version;
+        *builder.header_mut() = self.header.to_builder_header();
+        builder.body_mut().extend_from_slice(self.body);
+        builder
+    }
 
     /// creates a response from bytes
     ///
     /// this function is usefull for converting a byte buffer received from a TcpStream into an
-    /// actual http response which can be further handled
+    /// actual http response which can be further handled. Interim (1xx) responses in front of
+    /// the final one are skipped, see `Response::from_bytes_interim`.
+    ///
+    /// The body length is determined as described in RFC 9112 section 6.3: 204 and 304
+    /// responses have no body, a chunked body ends after its last chunk and trailers (the body
+    /// keeps its chunked framing), otherwise `Content-Length` gives the length. A response with
+    /// neither is delimited by the server closing the connection, see
+    /// `Response::from_bytes_eof`.
     ///
     /// # Error
     /// - `Error::NotEnoughData` is returned when the passed `buffer: &[u8]` does not contain the
-    ///   full request
+    ///   full response
+    /// - `Error::ReadUntilEof` is returned when the body ends when the connection is closed,
+    ///   read until the end of the stream and call `Response::from_bytes_eof`
     /// - `Error::InvalidUtf8` is returned when the http header is not valid utf-8
     /// - `Error::InvalidHeader` is returned when there is some other fuckup in the header (eg:
     ///   header is not formatted correctly)
     ///
     /// # Example
     /// ```
-    /// use reqse::{Response, Version, Status};
-    /// let raw_response = b"HTTP/1.1 200 OK\r\n\r\n";
+    /// use reqse::{Error, Response, Version, Status};
+    /// let raw_response = b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n";
     /// let response = Response::from_bytes(raw_response).unwrap();
     ///
     /// assert_eq!(response.version(), Version::Http1);
     /// assert!(response.body().is_empty());
     /// assert_eq!(response.status(), Status::Ok);
+    ///
+    /// let raw_response = b"HTTP/1.1 200 OK\r\n\r\nhello";
+    /// assert_eq!(Response::from_bytes(raw_response), Err(Error::ReadUntilEof));
     /// ```
     pub fn from_bytes(buf: &'a [u8]) -> Result<Self, Error> {
+        Self::parse(buf, Method::Get, false).map(|(response, _)| response)
+    }
+
+    /// creates a response from bytes which were read until the end of the stream
+    ///
+    /// works like `Response::from_bytes`, but a body without length indicator is the rest of
+    /// `buf` instead of an `Error::ReadUntilEof`
+    ///
+    /// # Example
+    /// ```
+    /// use reqse::Response;
+    /// let raw_response = b"HTTP/1.0 200 OK\r\n\r\nhello";
+    /// let response = Response::from_bytes_eof(raw_response).unwrap();
+    ///
+    /// assert_eq!(response.body(), b"hello");
+    /// ```
+    pub fn from_bytes_eof(buf: &'a [u8]) -> Result<Self, Error> {
+        Self::parse(buf, Method::Get, true).map(|(response, _)| response)
+    }
+
+    /// parses the response to a `request_method` request at the start of `buf` and returns it
+    /// together with the number of bytes it occupies, interim responses included
+    ///
+    /// `eof` tells whether the stream has ended, which delimits bodies without length indicator
+    pub(crate) fn parse(
+        buf: &'a [u8],
+        request_method: Method,
+        eof: bool,
+    ) -> Result<(Self, usize), Error> {
+        let (_, interim_len) = Self::parse_interim(buf)?;
+
+        let (version, status, header, head_len) = Self::parse_head(&buf[interim_len..])?;
+        let mid = interim_len + head_len;
+        let rest = &buf[mid..];
+
+        let len = match framing::response_body_length(request_method, status, &header)? {
+            BodyLength::Fixed(len) if rest.len() < len => return Err(Error::NotEnoughData),
+            BodyLength::Fixed(len) => len,
+            BodyLength::Chunked => chunked::decode(rest)?.2,
+            BodyLength::UntilEof if !eof => return Err(Error::ReadUntilEof),
+            BodyLength::UntilEof => rest.len(),
+        };
+
+        let response = Response {
+            version,
+            status,
+            header,
+            body: &rest[..len],
+        };
+
+        Ok((response, mid + len))
+    }
+
+    /// returns the interim (1xx) responses at the start of `buf`, which may be empty
+    ///
+    /// interim responses have no body, the final response follows after them
+    ///
+    /// # Example
+    /// ```
+    /// use reqse::{Response, Status};
+    /// let raw_response = b"HTTP/1.1 103 Early Hints\r\nLink: </a.css>; rel=preload\r\n\r\n\
+    ///     HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok";
+    ///
+    /// let interim = Response::from_bytes_interim(raw_response).unwrap();
+    /// assert_eq!(interim.len(), 1);
+    /// assert_eq!(interim[0].header().get("Link"), Some("</a.css>; rel=preload"));
+    ///
+    /// let response = Response::from_bytes(raw_response).unwrap();
+    /// assert_eq!(response.status(), Status::Ok);
+    /// ```
+    pub fn from_bytes_interim(buf: &'a [u8]) -> Result<Vec<Self>, Error> {
+        Self::parse_interim(buf).map(|(interim, _)| interim)
+    }
+
+    /// parses the interim responses at the start of `buf` and returns them together with the
+    /// number of bytes they occupy
+    pub(crate) fn parse_interim(buf: &'a [u8]) -> Result<(Vec<Self>, usize), Error> {
+        let mut interim = Vec::new();
+        let mut offset = 0;
+
+        loop {
+            match Self::parse_head(&buf[offset..]) {
+                Ok((version, status, header, len)) if status.is_interim() => {
+                    interim.push(Response::new(version, status, header, &[]));
+                    offset += len;
+                }
+                Ok(_) | Err(Error::NotEnoughData) => return Ok((interim, offset)),
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// creates a response to a `HEAD` request from bytes
+    ///
+    /// a response to `HEAD` never has a body even if its header announces one with
+    /// `Content-Length`, so only the interim responses, the status line and header are parsed
+    ///
+    /// # Error
+    /// see `Response::from_bytes`
+    ///
+    /// # Example
+    /// ```
+    /// use reqse::{Response, Status};
+    /// let raw_response = b"HTTP/1.1 200 OK\r\nContent-Length: 11\r\n\r\n";
+    /// let response = Response::from_bytes_head(raw_response).unwrap();
+    ///
+    /// assert_eq!(response.status(), Status::Ok);
+    /// assert_eq!(response.header().get("Content-Length"), Some("11"));
+    /// assert!(response.body().is_empty());
+    /// ```
+    pub fn from_bytes_head(buf: &'a [u8]) -> Result<Self, Error> {
+        Self::parse(buf, Method::Head, false).map(|(response, _)| response)
+    }
+
+    /// parses the status line and header at the start of `buf`
+    ///
+    /// returns the parsed parts and the length of the head including the empty line which
+    /// terminates it
+    pub(crate) fn parse_head(
+        buf: &'a [u8],
+    ) -> Result<(Version, Status, HeaderMap<'a>, usize), Error> {
         let mid = buf
             .windows(4)
             .enumerate()
@@ -57,7 +239,6 @@ impl<'a> Response<'a> {
             .ok_or(Error::NotEnoughData)?;
 
         let header = &buf[..mid];
-        let body = &buf[mid..];
 
         let header = std::str::from_utf8(header).ok().ok_or(Error::InvalidUtf8)?;
 
@@ -70,24 +251,69 @@ impl<'a> Response<'a> {
 
         let header = HeaderMap::new(header)?;
 
-        let content_len: usize = header
-            .get("Content-Length")
-            .unwrap_or("0")
-            .parse()
-            .ok()
-            .ok_or(Error::InvalidHeader)?;
+        Ok((version, status, header, mid))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_content_length() {
+        let raw = b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhelloHTTP/1.1";
+        let (response, len) = Response::parse(raw, Method::Get, false).unwrap();
+        assert_eq!(response.body(), b"hello");
+        assert_eq!(len, raw.len() - b"HTTP/1.1".len());
+
+        let short = b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhel";
+        assert_eq!(Response::from_bytes(short), Err(Error::NotEnoughData));
+    }
 
-        if content_len < body.len() {
-            return Err(Error::NotEnoughData);
+    #[test]
+    fn test_no_body() {
+        for status in ["204 No Content", "304 Not Modified"] {
+            let raw = format!("HTTP/1.1 {status}\r\nContent-Length: 5\r\n\r\n");
+            let response = Response::from_bytes(raw.as_bytes()).unwrap();
+            assert!(response.body().is_empty());
         }
 
-        let body = &body[..content_len];
+        let raw = b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\n";
+        let (response, len) = Response::parse(raw, Method::Head, false).unwrap();
+        assert!(response.body().is_empty());
+        assert_eq!(len, raw.len());
+    }
 
-        Ok(Response {
-            version,
-            status,
-            header,
-            body,
-        })
+    #[test]
+    fn test_switching_protocols() {
+        let raw = b"HTTP/1.1 101 Switching Protocols\r\nUpgrade: echo\r\n\r\nhello";
+        let (response, len) = Response::parse(raw, Method::Get, false).unwrap();
+        assert_eq!(response.status(), Status::SwitchingProtocols);
+        assert!(response.body().is_empty());
+        assert_eq!(&raw[len..], b"hello");
+    }
+
+    #[test]
+    fn test_until_eof() {
+        let raw = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: gzip\r\n\r\nhello";
+        assert_eq!(Response::from_bytes(raw), Err(Error::ReadUntilEof));
+        assert_eq!(Response::from_bytes_eof(raw).unwrap().body(), b"hello");
+    }
+
+    #[test]
+    fn test_chunked() {
+        let raw = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n\
+            5\r\nhello\r\n0\r\nChecksum: 1\r\n\r\n";
+        let (response, len) = Response::parse(raw, Method::Get, false).unwrap();
+        assert_eq!(len, raw.len());
+        assert_eq!(
+            response.body(),
+            b"5\r\nhello\r\n0\r\nChecksum: 1\r\n\r\n".as_slice()
+        );
+
+        assert_eq!(
+            Response::from_bytes(&raw[..raw.len() - 2]),
+            Err(Error::NotEnoughData)
+        );
     }
 }
@@ -1,6 +1,13 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    io::{self, IoSlice, Write},
+    net::TcpStream,
+};
 
-use crate::{Status, Version};
+use crate::{
+    Error, FileRange, Status, Version, chunked, connection, framing, sendfile, sse, syntax,
+    vectored, websocket,
+};
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct ResponseBuilder {
@@ -8,6 +15,9 @@ pub struct ResponseBuilder {
     status: Status,
     header: HashMap<String, String>,
     body: Vec<u8>,
+    file: Option<FileRange>,
+    trailers: HashMap<String, String>,
+    omit_body: bool,
 }
 
 impl ResponseBuilder {
@@ -17,9 +27,55 @@ impl ResponseBuilder {
             status,
             header: HashMap::new(),
             body: Vec::new(),
+            file: None,
+            trailers: HashMap::new(),
+            omit_body: false,
         }
     }
 
+    /// an interim `103 Early Hints` response, see `Interim`
+    pub fn early_hints() -> Self {
+        Self::new(Status::EarlyHints)
+    }
+
+    /// a `101 Switching Protocols` response accepting the upgrade to `protocol`, see `Upgrade`
+    pub fn switching_protocols(protocol: &str) -> Self {
+        let mut response = Self::new(Status::SwitchingProtocols);
+        response
+            .header
+            .insert("Connection".to_owned(), "Upgrade".to_owned());
+        response
+            .header
+            .insert("Upgrade".to_owned(), protocol.to_owned());
+        response
+    }
+
+    /// a `101 Switching Protocols` response accepting the WebSocket opening handshake with the
+    /// `Sec-WebSocket-Key` `key`, see `websocket::handshake`
+    pub fn websocket(key: &str) -> Self {
+        let mut response = Self::switching_protocols("websocket");
+        response.header.insert(
+            "Sec-WebSocket-Accept".to_owned(),
+            websocket::accept_key(key),
+        );
+        response
+    }
+
+    /// a `200 OK` response starting a `text/event-stream`, see `sse::EventStream`
+    ///
+    /// the events are written by a function registered with `Upgrade::on_upgrade` once the
+    /// head was sent, the stream ends when the connection is closed.
+    pub fn event_stream() -> Self {
+        let mut response = Self::ok();
+        response
+            .header
+            .insert("Content-Type".to_owned(), sse::CONTENT_TYPE.to_owned());
+        response
+            .header
+            .insert("Cache-Control".to_owned(), "no-cache".to_owned());
+        response
+    }
+
     pub fn ok() -> Self {
         Self::new(Status::Ok)
     }
@@ -40,10 +96,22 @@ impl ResponseBuilder {
         Self::new(Status::NotFound)
     }
 
+    pub fn payload_too_large() -> Self {
+        Self::new(Status::PayloadTooLarge)
+    }
+
+    pub fn expectation_failed() -> Self {
+        Self::new(Status::ExpectationFailed)
+    }
+
     pub fn internal_server_error() -> Self {
         Self::new(Status::InternalServerError)
     }
 
+    pub fn service_unavailable() -> Self {
+        Self::new(Status::ServiceUnavailable)
+    }
+
     pub fn header(&self) -> &HashMap<String, String> {
         &self.header
     }
@@ -76,33 +144,361 @@ impl ResponseBuilder {
         &mut self.body
     }
 
-    pub fn finish(mut self) -> Vec<u8> {
-        let mut buf = vec![];
+    /// whether `finish` leaves out the body, used when answering a `HEAD` request
+    ///
+    /// the `Content-Length` is still computed from the body, so the same handler can answer
+    /// `GET` and `HEAD` requests
+    ///
+    /// # Examples
+    /// ```
+    /// use reqse::ResponseBuilder;
+    ///
+    /// let mut response = ResponseBuilder::ok();
+    /// response.body_mut().extend_from_slice(b"Hello World");
+    /// *response.omit_body_mut() = true;
+    ///
+    /// assert_eq!(
+    ///     response.finish(),
+    ///     b"HTTP/1.1 200 OK\r\nContent-Length: 11\r\n\r\n"
+    /// );
+    /// ```
+    pub fn omit_body(&self) -> bool {
+        self.omit_body
+    }
+
+    pub fn omit_body_mut(&mut self) -> &mut bool {
+        &mut self.omit_body
+    }
+
+    /// a file range sent as body instead of `body`, see `send`
+    pub fn file(&self) -> Option<&FileRange> {
+        self.file.as_ref()
+    }
+
+    pub fn file_mut(&mut self) -> &mut Option<FileRange> {
+        &mut self.file
+    }
+
+    /// trailer fields sent after the body
+    ///
+    /// a response with trailers is sent with `Transfer-Encoding: chunked` and announces the
+    /// trailer names in a `Trailer` header. Fields which control framing, routing,
+    /// authentication or the processing of the content (eg: `Content-Length`, `Host`,
+    /// `Set-Cookie`) must not be trailers, `validate` rejects them. HTTP/1.0 has no chunked
+    /// transfer coding, so trailers are not sent when the version is `Version::Http0`.
+    ///
+    /// # Examples
+    /// ```
+    /// use reqse::ResponseBuilder;
+    ///
+    /// let mut response = ResponseBuilder::ok();
+    /// response.body_mut().extend_from_slice(b"Hello");
+    /// response
+    ///     .trailers_mut()
+    ///     .insert("Grpc-Status".to_owned(), "0".to_owned());
+    ///
+    /// let mut expected = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n".to_vec();
+    /// expected.extend_from_slice(b"Trailer: Grpc-Status\r\n\r\n");
+    /// expected.extend_from_slice(b"5\r\nHello\r\n0\r\nGrpc-Status: 0\r\n\r\n");
+    ///
+    /// assert_eq!(response.finish(), expected);
+    /// ```
+    pub fn trailers(&self) -> &HashMap<String, String> {
+        &self.trailers
+    }
+
+    pub fn trailers_mut(&mut self) -> &mut HashMap<String, String> {
+        &mut self.trailers
+    }
+
+    /// whether the body is left out, informational responses never have one
+    fn body_omitted(&self) -> bool {
+        self.omit_body || self.status.is_informational()
+    }
+
+    /// whether the body is sent with the chunked transfer coding, which is needed for trailers
+    ///
+    /// HTTP/1.0 does not know the chunked transfer coding, trailers are dropped there
+    fn chunked(&self) -> bool {
+        !self.trailers.is_empty()
+            && !self.status.is_informational()
+            && self.version != Version::Http0
+    }
+
+    fn body_len(&self) -> u64 {
+        match &self.file {
+            Some(file) => file.len(),
+            None => self.body.len() as u64,
+        }
+    }
+
+    /// the framing fields written instead of the ones in `header`
+    fn framing_headers(&self) -> Vec<(&'static str, String)> {
+        if self.status.is_informational() {
+            return Vec::new();
+        }
+
+        if self.chunked() {
+            return vec![
+                ("Transfer-Encoding", "chunked".to_owned()),
+                ("Trailer", chunked::trailer_header(&self.trailers)),
+            ];
+        }
+
+        let content_length = if self.file.is_some() || !self.body.is_empty() {
+            Some(self.body_len().to_string())
+        } else if self.omit_body {
+            // a HEAD handler may announce the length of a body it does not produce
+            self.header
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case("Content-Length"))
+                .filter(|(_, len)| framing::parse_content_length(len).is_ok())
+                .map(|(_, len)| len.clone())
+        } else if matches!(self.status, Status::NoContent | Status::NotModified) {
+            None
+        } else if sse::is_event_stream(self) {
+            // the events are written after the head, the connection closing ends them
+            None
+        } else {
+            // without a length the body of a response only ends when the connection is closed
+            Some("0".to_owned())
+        };
+
+        content_length
+            .map(|len| ("Content-Length", len))
+            .into_iter()
+            .collect()
+    }
+
+    /// the fields of `header` which are written, framing fields in any casing are left to
+    /// `framing_headers` and hop-by-hop fields named in `Connection` are dropped
+    fn headers(&self) -> impl Iterator<Item = (&str, &str)> {
+        let nominated = connection::nominated_fields(&self.header);
+
+        self.header
+            .iter()
+            .filter(move |(key, _)| {
+                !framing::is_framing_field(key) && !connection::is_nominated(&nominated, key)
+            })
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+
+    /// the bytes written before and after the body, they frame it as a chunk if needed
+    fn body_frame(&self) -> (Vec<u8>, Vec<u8>) {
+        if self.chunked() {
+            chunked::frame(self.body_len(), &self.trailers)
+        } else {
+            (Vec::new(), Vec::new())
+        }
+    }
+
+    /// serializes the status line and the header block including the empty line ending it
+    ///
+    /// `Content-Length` is set from the file range or the body, `0` for final responses
+    /// without body except `204`, `304` and event streams, or replaced by `Transfer-Encoding`
+    /// and `Trailer` if there are trailers
+    pub(crate) fn head(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(self.head_len());
 
         buf.extend_from_slice(self.version.to_static().as_bytes());
         buf.push(b' ');
         buf.extend_from_slice(self.status.to_static_str().as_bytes());
 
-        if self.body.is_empty() {
-            self.header.remove("Content-Length");
-        } else {
-            self.header
-                .insert("Content-Length".to_owned(), self.body.len().to_string());
+        for (key, value) in self.headers() {
+            vectored::push_header_line(&mut buf, key, value);
         }
 
-        for (key, value) in self.header.into_iter() {
-            let mut key = key.into_bytes();
-            let mut value = value.into_bytes();
-
-            buf.extend_from_slice(b"\r\n");
-            buf.append(&mut key);
-            buf.extend_from_slice(b": ");
-            buf.append(&mut value);
+        for (key, value) in self.framing_headers() {
+            vectored::push_header_line(&mut buf, key, &value);
         }
 
         buf.extend_from_slice(b"\r\n\r\n");
-        buf.append(&mut self.body);
 
         buf
     }
+
+    fn head_len(&self) -> usize {
+        let status_line = self.version.to_static().len() + 1 + self.status.to_static_str().len();
+        let headers = self
+            .headers()
+            .map(|(key, value)| vectored::header_line_len(key, value))
+            .sum::<usize>();
+        let framing_headers = self
+            .framing_headers()
+            .iter()
+            .map(|(key, value)| vectored::header_line_len(key, value))
+            .sum::<usize>();
+
+        status_line + headers + framing_headers + 4
+    }
+
+    /// number of bytes `write_to` writes
+    ///
+    /// # Examples
+    /// ```
+    /// use reqse::ResponseBuilder;
+    ///
+    /// let mut response = ResponseBuilder::ok();
+    /// response.body_mut().extend_from_slice(b"Hello World");
+    ///
+    /// assert_eq!(response.serialized_len(), response.clone().finish().len());
+    /// ```
+    pub fn serialized_len(&self) -> usize {
+        if self.body_omitted() {
+            return self.head_len();
+        }
+
+        let (prefix, suffix) = self.body_frame();
+
+        self.head_len() + prefix.len() + self.body_len() as usize + suffix.len()
+    }
+
+    /// checks that the header can be sent without changing the meaning of the message
+    ///
+    /// field names have to be tokens and field values must not contain control characters
+    /// like CR or LF, which would let a value taken from user input inject fields or split the
+    /// response. Trailers must not be one of the fields forbidden in trailers, see
+    /// `trailers_mut`.
+    ///
+    /// # Error
+    /// - `Error::InvalidHeader` if a field name or value is invalid
+    ///
+    /// # Examples
+    /// ```
+    /// use reqse::{Error, ResponseBuilder};
+    ///
+    /// let mut response = ResponseBuilder::new(reqse::Status::Found);
+    /// response
+    ///     .header_mut()
+    ///     .insert("Location".to_owned(), "/\r\nSet-Cookie: a=b".to_owned());
+    ///
+    /// assert_eq!(response.validate(), Err(Error::InvalidHeader));
+    /// assert_eq!(response.try_finish(), Err(Error::InvalidHeader));
+    /// ```
+    pub fn validate(&self) -> Result<(), Error> {
+        syntax::validate_header(&self.header)?;
+        chunked::validate_trailers(&self.trailers)
+    }
+
+    /// writes the serialized response to `writer`
+    ///
+    /// the response is validated first, see `validate`. The head and the body are handed to
+    /// `writer` with a single `write_vectored`, so the body is never copied into an
+    /// intermediate buffer. A file range is copied through a buffer, use `send` to send it with
+    /// `sendfile(2)`.
+    ///
+    /// # Examples
+    /// ```
+    /// use reqse::ResponseBuilder;
+    ///
+    /// let mut buf = Vec::new();
+    ///
+    /// for body in ["Hello", "World"] {
+    ///     let mut response = ResponseBuilder::ok();
+    ///     response.body_mut().extend_from_slice(body.as_bytes());
+    ///
+    ///     buf.clear();
+    ///     response.write_to(&mut buf).unwrap();
+    /// }
+    ///
+    /// assert_eq!(buf, b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nWorld");
+    /// ```
+    pub fn write_to<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<()> {
+        self.validate()?;
+        self.write_unchecked(writer)
+    }
+
+    fn write_unchecked<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<()> {
+        let head = self.head();
+
+        if self.body_omitted() {
+            return writer.write_all(&head);
+        }
+
+        let (prefix, suffix) = self.body_frame();
+
+        match &self.file {
+            Some(file) => {
+                vectored::write_all_vectored(
+                    writer,
+                    &mut [IoSlice::new(&head), IoSlice::new(&prefix)],
+                )?;
+                file.copy_to(writer)?;
+                writer.write_all(&suffix)
+            }
+            None => vectored::write_all_vectored(
+                writer,
+                &mut [
+                    IoSlice::new(&head),
+                    IoSlice::new(&prefix),
+                    IoSlice::new(&self.body),
+                    IoSlice::new(&suffix),
+                ],
+            ),
+        }
+    }
+
+    /// serializes the response after validating it, see `validate` and `finish`
+    ///
+    /// # Error
+    /// see `ResponseBuilder::validate`
+    pub fn try_finish(self) -> Result<Vec<u8>, Error> {
+        self.validate()?;
+        Ok(self.finish())
+    }
+
+    /// serializes the response without validating it, see `try_finish`
+    ///
+    /// a file range is never read here, the response is serialized as if it had none. Use
+    /// `write_to` or `send` for responses with a file range, they report a failing read.
+    pub fn finish(mut self) -> Vec<u8> {
+        self.file = None;
+
+        let mut buf = Vec::with_capacity(self.serialized_len());
+        buf.extend_from_slice(&self.head());
+
+        if !self.body_omitted() {
+            let (prefix, suffix) = self.body_frame();
+            buf.extend_from_slice(&prefix);
+            buf.extend_from_slice(&self.body);
+            buf.extend_from_slice(&suffix);
+        }
+
+        buf
+    }
+
+    /// writes the response to `stream`
+    ///
+    /// the response is validated first, see `validate`. A file range is sent with `sendfile(2)`
+    /// on Linux so its bytes never pass through userspace, and copied through a buffer
+    /// everywhere else.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use reqse::{FileRange, ResponseBuilder};
+    /// use std::{fs::File, net::TcpStream};
+    ///
+    /// let mut stream = TcpStream::connect("localhost:3000").unwrap();
+    ///
+    /// let mut response = ResponseBuilder::ok();
+    /// let file = File::open("large.iso").unwrap();
+    /// *response.file_mut() = Some(FileRange::from_file(file).unwrap());
+    /// response.send(&mut stream).unwrap();
+    /// ```
+    pub fn send(self, stream: &mut TcpStream) -> io::Result<()> {
+        match &self.file {
+            Some(file) if !self.body_omitted() => {
+                self.validate()?;
+
+                let (prefix, suffix) = self.body_frame();
+                vectored::write_all_vectored(
+                    stream,
+                    &mut [IoSlice::new(&self.head()), IoSlice::new(&prefix)],
+                )?;
+                sendfile::send_file(stream, file)?;
+                stream.write_all(&suffix)
+            }
+            _ => self.write_to(stream),
+        }
+    }
 }
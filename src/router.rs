@@ -0,0 +1,549 @@
+use std::{collections::HashMap, fmt, io, sync::Arc};
+
+use crate::{Handler, Method, Request, ResponseBuilder, Status};
+
+type Route = Arc<dyn Handler + Send + Sync>;
+
+/// dispatches requests to handlers (usually plain functions) by method and path
+///
+/// patterns are matched segment by segment:
+/// - `/users` matches exactly the segment `users`
+/// - `/users/:id` captures a single segment as the parameter `id`
+/// - `/files/*path` captures the rest of the path (including slashes) as `path`, a wildcard has
+///   to be the last segment of a pattern
+///
+/// literal segments take precedence over parameters, parameters over wildcards, as long as they
+/// have a route for the method of the request. Captured parameters are available through
+/// `Request::param`.
+///
+/// if the path matches but no route is registered for the method the router answers with
+/// `405 Method Not Allowed` and an `Allow` header listing the methods of every matching
+/// pattern. `OPTIONS` requests are answered with `204 No Content` and an `Allow` header unless
+/// an `OPTIONS` route is registered. `HEAD` requests without a `HEAD` route are passed to the
+/// `GET` route, the body of its response is left out by `ResponseBuilder::finish`.
+///
+/// # Examples
+/// ```
+/// use reqse::{Method, Request, ResponseBuilder, Router, Status};
+/// use std::io;
+///
+/// fn user(request: Request) -> io::Result<ResponseBuilder> {
+///     let mut response = ResponseBuilder::ok();
+///     response
+///         .body_mut()
+///         .extend_from_slice(request.param("id").unwrap().as_bytes());
+///     Ok(response)
+/// }
+///
+/// let mut router = Router::new();
+/// router.add(Method::Get, "/users/:id", user);
+///
+/// let request = Request::from_bytes(b"GET /users/42 HTTP/1.1\r\n\r\n").unwrap();
+/// let response = router.handle(request).unwrap();
+/// assert_eq!(response.body(), b"42");
+///
+/// let request = Request::from_bytes(b"DELETE /users/42 HTTP/1.1\r\n\r\n").unwrap();
+/// let response = router.handle(request).unwrap();
+/// assert_eq!(response.status(), Status::MethodNotAllowed);
+/// assert_eq!(response.header()["Allow"], "GET, HEAD, OPTIONS");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Router {
+    root: Node,
+}
+
+#[derive(Clone, Default)]
+struct Node {
+    routes: Vec<(Method, Route)>,
+    children: HashMap<String, Node>,
+    param: Option<(String, Box<Node>)>,
+    wildcard: Option<(String, Vec<(Method, Route)>)>,
+}
+
+impl Router {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// registers `route` for requests with `method` whose path matches `pattern`
+    ///
+    /// # Panics
+    /// - if a route for `method` and `pattern` is already registered
+    /// - if `pattern` names a parameter differently than an already registered pattern at the
+    ///   same position (eg: `/users/:id` and `/users/:name/posts`)
+    /// - if a wildcard is not the last segment or a parameter has no name
+    pub fn add<H>(&mut self, method: Method, pattern: &str, handler: H) -> &mut Self
+    where
+        H: Handler + Send + Sync + 'static,
+    {
+        let route: Route = Arc::new(handler);
+        let mut node = &mut self.root;
+        let mut segments = segments(pattern).peekable();
+
+        while let Some(segment) = segments.next() {
+            if let Some(name) = segment.strip_prefix('*') {
+                assert!(
+                    segments.peek().is_none(),
+                    "wildcard has to be the last segment in route pattern `{}`",
+                    pattern
+                );
+
+                let (existing, routes) = node
+                    .wildcard
+                    .get_or_insert_with(|| (name.to_owned(), Vec::new()));
+
+                assert_param(existing, name, pattern);
+                insert_route(routes, method, route, pattern);
+
+                return self;
+            }
+
+            node = match segment.strip_prefix(':') {
+                Some(name) => {
+                    let (existing, child) = node
+                        .param
+                        .get_or_insert_with(|| (name.to_owned(), Box::default()));
+
+                    assert_param(existing, name, pattern);
+                    child
+                }
+                None => node.children.entry(segment.to_owned()).or_default(),
+            };
+        }
+
+        insert_route(&mut node.routes, method, route, pattern);
+
+        self
+    }
+
+    pub fn get<H>(&mut self, pattern: &str, handler: H) -> &mut Self
+    where
+        H: Handler + Send + Sync + 'static,
+    {
+        self.add(Method::Get, pattern, handler)
+    }
+
+    pub fn post<H>(&mut self, pattern: &str, handler: H) -> &mut Self
+    where
+        H: Handler + Send + Sync + 'static,
+    {
+        self.add(Method::Post, pattern, handler)
+    }
+
+    pub fn put<H>(&mut self, pattern: &str, handler: H) -> &mut Self
+    where
+        H: Handler + Send + Sync + 'static,
+    {
+        self.add(Method::Put, pattern, handler)
+    }
+
+    pub fn delete<H>(&mut self, pattern: &str, handler: H) -> &mut Self
+    where
+        H: Handler + Send + Sync + 'static,
+    {
+        self.add(Method::Delete, pattern, handler)
+    }
+
+    /// dispatches `request` to the matching route
+    ///
+    /// answers with `404 Not Found` if no pattern matches the path, see `Router` for `405` and
+    /// `OPTIONS` handling
+    pub fn handle(&self, mut request: Request) -> io::Result<ResponseBuilder> {
+        let method = request.method();
+
+        match self.resolve(&mut request) {
+            Ok(route) => {
+                let mut response = route.call(request)?;
+                *response.omit_body_mut() |= method == Method::Head;
+                Ok(response)
+            }
+            Err(response) => Ok(*response),
+        }
+    }
+
+    /// finds the route for `request` and stores the captured parameters in it
+    ///
+    /// returns the response if no route matches, see `Router::handle`
+    fn resolve(&self, request: &mut Request) -> Result<&Route, Box<ResponseBuilder>> {
+        let path = request.path();
+        let method = request.method();
+        let mut captured = Vec::new();
+
+        if let Some(route) = self.root.find(path, 0, &mut captured, method) {
+            for (name, value) in captured {
+                request.params_mut().insert(name.to_owned(), value);
+            }
+
+            return Ok(route);
+        }
+
+        let mut candidates = Vec::new();
+        self.root.find_all(path, 0, &mut candidates);
+
+        if candidates.is_empty() {
+            return Err(Box::new(ResponseBuilder::not_found()));
+        }
+
+        let mut response = match method {
+            Method::Options => ResponseBuilder::new(Status::NoContent),
+            _ => ResponseBuilder::new(Status::MethodNotAllowed),
+        };
+
+        response
+            .header_mut()
+            .insert("Allow".to_owned(), allow(&candidates));
+
+        Err(Box::new(response))
+    }
+}
+
+impl Handler for Router {
+    fn call(&self, request: Request) -> io::Result<ResponseBuilder> {
+        self.handle(request)
+    }
+
+    /// rejects requests without a matching route right away and asks the route otherwise
+    fn check_continue(&self, request: &Request) -> Option<ResponseBuilder> {
+        let mut request = request.clone();
+
+        match self.resolve(&mut request) {
+            Ok(route) => route.check_continue(&request),
+            Err(response) => Some(*response),
+        }
+    }
+}
+
+impl fmt::Debug for Node {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let methods = |routes: &[(Method, Route)]| {
+            routes.iter().map(|(method, _)| *method).collect::<Vec<_>>()
+        };
+
+        f.debug_struct("Node")
+            .field("routes", &methods(&self.routes))
+            .field("children", &self.children)
+            .field("param", &self.param)
+            .field(
+                "wildcard",
+                &self
+                    .wildcard
+                    .as_ref()
+                    .map(|(name, routes)| (name, methods(routes))),
+            )
+            .finish()
+    }
+}
+
+impl Node {
+    /// finds the route for `method` registered for `path`, starting at byte `offset`, and
+    /// collects the captured parameters
+    ///
+    /// a pattern without a route for `method` does not hide the ones with lower precedence
+    fn find<'r, 'a>(
+        &'r self,
+        path: &'a str,
+        offset: usize,
+        captured: &mut Vec<(&'r str, &'a str)>,
+        method: Method,
+    ) -> Option<&'r Route> {
+        let (start, trimmed) = trim_slashes(path, offset);
+
+        if trimmed.is_empty() {
+            if let Some(route) = route(&self.routes, method) {
+                return Some(route);
+            }
+        } else {
+            let end = trimmed.find('/').map(|i| start + i).unwrap_or(path.len());
+            let segment = &path[start..end];
+
+            if let Some(route) = self
+                .children
+                .get(segment)
+                .and_then(|child| child.find(path, end, captured, method))
+            {
+                return Some(route);
+            }
+
+            if let Some((name, child)) = &self.param {
+                let len = captured.len();
+                captured.push((name, segment));
+
+                if let Some(route) = child.find(path, end, captured, method) {
+                    return Some(route);
+                }
+
+                captured.truncate(len);
+            }
+        }
+
+        if let Some((name, routes)) = &self.wildcard
+            && let Some(route) = route(routes, method)
+        {
+            captured.push((name, trimmed));
+            return Some(route);
+        }
+
+        None
+    }
+
+    /// collects the routes of every pattern matching `path`, starting at byte `offset`
+    fn find_all<'r>(&'r self, path: &str, offset: usize, found: &mut Vec<&'r [(Method, Route)]>) {
+        let (start, trimmed) = trim_slashes(path, offset);
+
+        if trimmed.is_empty() {
+            if !self.routes.is_empty() {
+                found.push(&self.routes);
+            }
+        } else {
+            let end = trimmed.find('/').map(|i| start + i).unwrap_or(path.len());
+
+            if let Some(child) = self.children.get(&path[start..end]) {
+                child.find_all(path, end, found);
+            }
+
+            if let Some((_, child)) = &self.param {
+                child.find_all(path, end, found);
+            }
+        }
+
+        if let Some((_, routes)) = &self.wildcard {
+            found.push(routes);
+        }
+    }
+}
+
+/// skips the slashes at byte `offset` of `path`, returns where the rest starts and the rest
+fn trim_slashes(path: &str, offset: usize) -> (usize, &str) {
+    let rest = &path[offset..];
+    let trimmed = rest.trim_start_matches('/');
+
+    (offset + rest.len() - trimmed.len(), trimmed)
+}
+
+/// the route for `method` among `routes`, `HEAD` falls back to `GET`
+fn route(routes: &[(Method, Route)], method: Method) -> Option<&Route> {
+    let route = |method| {
+        routes
+            .iter()
+            .find(|(m, _)| *m == method)
+            .map(|(_, route)| route)
+    };
+
+    match method {
+        Method::Head => route(Method::Head).or_else(|| route(Method::Get)),
+        _ => route(method),
+    }
+}
+
+fn segments(pattern: &str) -> impl Iterator<Item = &str> {
+    pattern.split('/').filter(|segment| !segment.is_empty())
+}
+
+fn assert_param(existing: &str, name: &str, pattern: &str) {
+    assert!(
+        !name.is_empty(),
+        "parameter without name in route pattern `{}`",
+        pattern
+    );
+    assert!(
+        existing == name,
+        "parameter `{}` in route pattern `{}` conflicts with existing parameter `{}`",
+        name,
+        pattern,
+        existing
+    );
+}
+
+fn insert_route(routes: &mut Vec<(Method, Route)>, method: Method, route: Route, pattern: &str) {
+    assert!(
+        routes.iter().all(|(m, _)| *m != method),
+        "route `{} {}` is already registered",
+        method,
+        pattern
+    );
+
+    routes.push((method, route));
+}
+
+/// value of the `Allow` header for a path matched by patterns with `candidates`
+fn allow(candidates: &[&[(Method, Route)]]) -> String {
+    let mut methods = Vec::new();
+    for (method, _) in candidates.iter().flat_map(|routes| routes.iter()) {
+        if !methods.contains(method) {
+            methods.push(*method);
+        }
+    }
+
+    if methods.contains(&Method::Get) && !methods.contains(&Method::Head) {
+        let get = methods.iter().position(|m| *m == Method::Get).unwrap_or(0);
+        methods.insert(get + 1, Method::Head);
+    }
+
+    if !methods.contains(&Method::Options) {
+        methods.push(Method::Options);
+    }
+
+    methods
+        .iter()
+        .map(|method| method.to_static_str())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn echo_params(request: Request) -> io::Result<ResponseBuilder> {
+        let mut response = ResponseBuilder::ok();
+        let params = request
+            .params()
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect::<Vec<_>>()
+            .join("&");
+        response.body_mut().extend_from_slice(params.as_bytes());
+        Ok(response)
+    }
+
+    fn static_route(_: Request) -> io::Result<ResponseBuilder> {
+        let mut response = ResponseBuilder::ok();
+        response.body_mut().extend_from_slice(b"static");
+        Ok(response)
+    }
+
+    fn router() -> Router {
+        let mut router = Router::new();
+        router
+            .get("/", static_route)
+            .get("/users/me", static_route)
+            .get("/users/:id", echo_params)
+            .put("/users/:id", echo_params)
+            .get("/users/:id/posts/:post", echo_params)
+            .get("/files/*path", echo_params);
+        router
+    }
+
+    fn handle(router: &Router, raw_request: &str) -> ResponseBuilder {
+        let request = Request::from_bytes(raw_request.as_bytes()).unwrap();
+        router.handle(request).unwrap()
+    }
+
+    #[test]
+    fn test_match() {
+        let router = router();
+
+        let response = handle(&router, "GET / HTTP/1.1\r\n\r\n");
+        assert_eq!(response.body(), b"static");
+
+        let response = handle(&router, "GET /users/me HTTP/1.1\r\n\r\n");
+        assert_eq!(response.body(), b"static");
+
+        let response = handle(&router, "GET /users/42?full=1 HTTP/1.1\r\n\r\n");
+        assert_eq!(response.body(), b"id=42");
+
+        let response = handle(&router, "GET /users/me/posts/7 HTTP/1.1\r\n\r\n");
+        assert_eq!(response.body(), b"id=me&post=7");
+
+        let response = handle(&router, "GET /files/css/main.css HTTP/1.1\r\n\r\n");
+        assert_eq!(response.body(), b"path=css/main.css");
+
+        let response = handle(&router, "GET /unknown HTTP/1.1\r\n\r\n");
+        assert_eq!(response.status(), Status::NotFound);
+    }
+
+    #[test]
+    fn test_method_not_allowed_and_options() {
+        let router = router();
+
+        let response = handle(&router, "DELETE /users/42 HTTP/1.1\r\n\r\n");
+        assert_eq!(response.status(), Status::MethodNotAllowed);
+        assert_eq!(response.header()["Allow"], "GET, HEAD, PUT, OPTIONS");
+
+        let response = handle(&router, "OPTIONS /users/42 HTTP/1.1\r\n\r\n");
+        assert_eq!(response.status(), Status::NoContent);
+        assert_eq!(response.header()["Allow"], "GET, HEAD, PUT, OPTIONS");
+    }
+
+    #[test]
+    fn test_literal_falls_back_to_param() {
+        let router = router();
+
+        // `/users/me` only has a `GET` route, `PUT` goes to `/users/:id`
+        let response = handle(&router, "PUT /users/me HTTP/1.1\r\n\r\n");
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.body(), b"id=me");
+
+        let response = handle(&router, "DELETE /users/me HTTP/1.1\r\n\r\n");
+        assert_eq!(response.status(), Status::MethodNotAllowed);
+        assert_eq!(response.header()["Allow"], "GET, HEAD, PUT, OPTIONS");
+    }
+
+    #[test]
+    fn test_head_uses_get_route() {
+        let router = router();
+
+        let response = handle(&router, "HEAD /users/42 HTTP/1.1\r\n\r\n");
+        assert_eq!(response.status(), Status::Ok);
+        assert!(response.omit_body());
+        assert_eq!(
+            response.finish(),
+            b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\n"
+        );
+    }
+
+    /// accepts uploads to every id but `big`
+    struct Upload;
+
+    impl Handler for Upload {
+        fn call(&self, _: Request) -> io::Result<ResponseBuilder> {
+            Ok(ResponseBuilder::ok())
+        }
+
+        fn check_continue(&self, request: &Request) -> Option<ResponseBuilder> {
+            (request.param("id") == Some("big")).then(ResponseBuilder::payload_too_large)
+        }
+    }
+
+    #[test]
+    fn test_check_continue() {
+        let mut router = router();
+        router.post("/uploads/:id", Upload);
+
+        let check = |raw_request: &str| {
+            let request = Request::from_bytes_head(raw_request.as_bytes()).unwrap();
+            router
+                .check_continue(&request)
+                .map(|response| response.status())
+        };
+
+        assert_eq!(check("POST /uploads/small HTTP/1.1\r\n\r\n"), None);
+        assert_eq!(
+            check("POST /uploads/big HTTP/1.1\r\n\r\n"),
+            Some(Status::PayloadTooLarge)
+        );
+        assert_eq!(
+            check("POST /users/42 HTTP/1.1\r\n\r\n"),
+            Some(Status::MethodNotAllowed)
+        );
+        assert_eq!(
+            check("POST /unknown HTTP/1.1\r\n\r\n"),
+            Some(Status::NotFound)
+        );
+        assert_eq!(check("GET /users/42 HTTP/1.1\r\n\r\n"), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "conflicts with existing parameter")]
+    fn test_conflicting_params() {
+        let mut router = router();
+        router.get("/users/:name/friends", static_route);
+    }
+
+    #[test]
+    #[should_panic(expected = "already registered")]
+    fn test_duplicate_route() {
+        let mut router = router();
+        router.get("/users/:id", static_route);
+    }
+}
@@ -0,0 +1,185 @@
+use std::{io, net::TcpStream};
+
+use crate::FileRange;
+
+/// sends `range` over `stream`
+///
+/// on Linux the bytes are moved by the kernel with `sendfile(2)` without ever being copied into
+/// userspace, everywhere else (and for files `sendfile` refuses) they are copied through a
+/// buffer
+pub(crate) fn send_file(stream: &mut TcpStream, range: &FileRange) -> io::Result<()> {
+    #[cfg(all(target_os = "linux", target_pointer_width = "64"))]
+    {
+        match linux::send_file(stream, range) {
+            Err(err) if linux::unsupported(&err) => (),
+            result => return result,
+        }
+    }
+
+    range.copy_to(stream)
+}
+
+#[cfg(all(target_os = "linux", target_pointer_width = "64"))]
+mod linux {
+    use std::{
+        io,
+        net::TcpStream,
+        os::{
+            fd::AsRawFd,
+            raw::{c_int, c_long},
+        },
+    };
+
+    use crate::FileRange;
+
+    const EINVAL: i32 = 22;
+    const ENOSYS: i32 = 38;
+
+    /// `sendfile` transfers at most this many bytes per call
+    const MAX_CHUNK: usize = 0x7fff_f000;
+
+    unsafe extern "C" {
+        fn sendfile(out_fd: c_int, in_fd: c_int, offset: *mut c_long, count: usize) -> isize;
+    }
+
+    /// whether `send_file` failed before sending anything because the file can not be used
+    /// with `sendfile`
+    pub(super) fn unsupported(err: &io::Error) -> bool {
+        matches!(err.raw_os_error(), Some(EINVAL | ENOSYS))
+    }
+
+    pub(super) fn send_file(stream: &mut TcpStream, range: &FileRange) -> io::Result<()> {
+        let mut offset = c_long::try_from(range.offset())
+            .map_err(|_| io::Error::from(io::ErrorKind::InvalidInput))?;
+        let mut remaining = range.len();
+        let mut sent_any = false;
+
+        while remaining > 0 {
+            let count = usize::try_from(remaining)
+                .unwrap_or(MAX_CHUNK)
+                .min(MAX_CHUNK);
+
+            // SAFETY: both descriptors stay open for the duration of the call and `offset` is a
+            // valid pointer to a local
+            let sent = unsafe {
+                sendfile(
+                    stream.as_raw_fd(),
+                    range.file().as_raw_fd(),
+                    &mut offset,
+                    count,
+                )
+            };
+
+            match sent {
+                -1 => {
+                    let err = io::Error::last_os_error();
+                    match err.kind() {
+                        io::ErrorKind::Interrupted => continue,
+                        // a fallback would resend what was already sent
+                        _ if sent_any && unsupported(&err) => {
+                            return Err(io::Error::other(err));
+                        }
+                        _ => return Err(err),
+                    }
+                }
+                0 => return Err(io::ErrorKind::UnexpectedEof.into()),
+                sent => {
+                    sent_any = true;
+                    remaining -= sent as u64;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::{
+        fs::{self, File},
+        io::Read,
+        net::TcpListener,
+        thread,
+    };
+
+    #[test]
+    fn test_send_file() {
+        let path = std::env::temp_dir().join(format!("reqse-sendfile-{}", std::process::id()));
+        let content = (0..100_000).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+        fs::write(&path, &content).unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let range = FileRange::new(File::open(&path).unwrap(), 10, 90_000);
+        let sender = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            send_file(&mut stream, &range).unwrap();
+        });
+
+        let mut received = Vec::new();
+        TcpStream::connect(addr)
+            .unwrap()
+            .read_to_end(&mut received)
+            .unwrap();
+        sender.join().unwrap();
+
+        assert_eq!(received, &content[10..90_010]);
+
+        let short = FileRange::new(File::open(&path).unwrap(), 99_990, 20);
+        let mut buf = Vec::new();
+        assert!(short.copy_to(&mut buf).is_err());
+
+        // a truncated body would contradict the announced length
+        let mut response = crate::ResponseBuilder::ok();
+        *response.file_mut() = Some(short);
+        let err = response.clone().write_to(&mut Vec::new()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+        // serializing to a buffer leaves the file out instead of failing
+        assert_eq!(
+            response.finish(),
+            b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n"
+        );
+
+        let _ = fs::remove_file(path);
+    }
+
+    /// copies a clone of `range` in the middle of every copy it receives
+    struct Interleave {
+        range: FileRange,
+        received: Vec<u8>,
+    }
+
+    impl io::Write for Interleave {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.range.clone().copy_to(&mut Vec::new())?;
+            self.received.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_copy_shared_file_interleaved() {
+        let path = std::env::temp_dir().join(format!("reqse-copy-{}", std::process::id()));
+        let content = (0..200_000).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+        fs::write(&path, &content).unwrap();
+
+        // clones share the file, copying one must not move the position of another
+        let range = FileRange::new(File::open(&path).unwrap(), 10, 150_000);
+        let mut writer = Interleave {
+            range: range.clone(),
+            received: Vec::new(),
+        };
+        range.copy_to(&mut writer).unwrap();
+        assert!(writer.received == content[10..150_010]);
+
+        let _ = fs::remove_file(path);
+    }
+}
@@ -0,0 +1,1100 @@
+use std::{
+    any::Any,
+    io::{self, ErrorKind, Read, Write},
+    net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc::{self, Receiver, TrySendError},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    Error, Handler, Interim, Method, Request, ResponseBuilder, Status, Upgrade, Upgraded, Version,
+    connection, should_keep_alive, sse, upgrade::OnUpgrade,
+};
+
+type BoxedHandler = Arc<dyn Handler + Send + Sync>;
+type OnError = Arc<dyn Fn(io::Error) + Send + Sync>;
+
+/// how often an idle keep-alive connection checks whether the server is shutting down
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// a blocking HTTP/1.1 server backed by a fixed pool of worker threads
+///
+/// accepted connections are put into a bounded queue from which the workers take them. If the
+/// queue is full the connection is answered with `503 Service Unavailable` right away instead of
+/// letting the client wait for a free worker.
+///
+/// requests are parsed with `Request::from_bytes_strict`, ambiguous ones are answered with
+/// `400 Bad Request` and the connection is closed. Requests sent with `Expect: 100-continue`
+/// get `100 Continue` once their header arrived, unless `Handler::check_continue` rejects them.
+/// A handler can switch the connection to another protocol or stream server-sent events, see
+/// `Upgrade` and `Server::max_upgraded`.
+///
+/// # Examples
+/// ```no_run
+/// use reqse::{Method, Request, ResponseBuilder, Server};
+/// use std::io;
+///
+/// fn router(request: Request) -> io::Result<ResponseBuilder> {
+///     match (request.method(), request.uri()) {
+///         (Method::Get, "/") => {
+///             let mut response = ResponseBuilder::ok();
+///             response.body_mut().extend_from_slice(b"Hello World");
+///             Ok(response)
+///         }
+///         _ => Ok(ResponseBuilder::not_found()),
+///     }
+/// }
+///
+/// let server = Server::bind("localhost:3000", router).unwrap();
+/// server.run().unwrap();
+/// ```
+pub struct Server {
+    listener: TcpListener,
+    handler: BoxedHandler,
+    workers: usize,
+    queue_len: usize,
+    max_request_len: usize,
+    keep_alive_timeout: Duration,
+    read_timeout: Duration,
+    max_upgraded: usize,
+    on_error: Option<OnError>,
+    shutdown: Arc<AtomicBool>,
+}
+
+impl Server {
+    /// creates a server which accepts connections on `listener` and answers every request with
+    /// `handler`
+    pub fn new<H>(listener: TcpListener, handler: H) -> Self
+    where
+        H: Handler + Send + Sync + 'static,
+    {
+        Self {
+            listener,
+            handler: Arc::new(handler),
+            workers: 4,
+            queue_len: 64,
+            max_request_len: 64 * 1024,
+            keep_alive_timeout: Duration::from_secs(5),
+            read_timeout: Duration::from_secs(30),
+            max_upgraded: 256,
+            on_error: None,
+            shutdown: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// binds a `TcpListener` to `addr` and creates a server on top of it, see `Server::new`
+    pub fn bind<A, H>(addr: A, handler: H) -> io::Result<Self>
+    where
+        A: ToSocketAddrs,
+        H: Handler + Send + Sync + 'static,
+    {
+        Ok(Self::new(TcpListener::bind(addr)?, handler))
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// number of worker threads handling connections
+    pub fn workers(&self) -> usize {
+        self.workers
+    }
+
+    pub fn workers_mut(&mut self) -> &mut usize {
+        &mut self.workers
+    }
+
+    /// number of accepted connections which may wait for a free worker before the server starts
+    /// answering with `503 Service Unavailable`
+    pub fn queue_len(&self) -> usize {
+        self.queue_len
+    }
+
+    pub fn queue_len_mut(&mut self) -> &mut usize {
+        &mut self.queue_len
+    }
+
+    /// maximum number of bytes a single request (header and body) may occupy, larger requests are
+    /// answered with `413 Payload Too Large` as soon as their header announces the length, with
+    /// `400 Bad Request` otherwise
+    pub fn max_request_len(&self) -> usize {
+        self.max_request_len
+    }
+
+    pub fn max_request_len_mut(&mut self) -> &mut usize {
+        &mut self.max_request_len
+    }
+
+    /// how long an idle connection is kept open waiting for the next request
+    pub fn keep_alive_timeout(&self) -> Duration {
+        self.keep_alive_timeout
+    }
+
+    pub fn keep_alive_timeout_mut(&mut self) -> &mut Duration {
+        &mut self.keep_alive_timeout
+    }
+
+    /// how long a started request may go without receiving any bytes, the request is answered
+    /// with `408 Request Timeout` afterwards
+    pub fn read_timeout(&self) -> Duration {
+        self.read_timeout
+    }
+
+    pub fn read_timeout_mut(&mut self) -> &mut Duration {
+        &mut self.read_timeout
+    }
+
+    /// maximum number of upgraded connections and event streams served at the same time, each
+    /// of them runs on a thread of its own
+    ///
+    /// further upgrades are refused with `503 Service Unavailable` until one of them ends
+    pub fn max_upgraded(&self) -> usize {
+        self.max_upgraded
+    }
+
+    pub fn max_upgraded_mut(&mut self) -> &mut usize {
+        &mut self.max_upgraded
+    }
+
+    /// registers `on_error` to be called with the IO errors which end a connection, eg: the
+    /// client resetting it or a file body which can not be read, and with the errors the
+    /// handler returns, which are answered with `500 Internal Server Error`
+    ///
+    /// a panic while handling a connection is caught and reported here as well. Such errors
+    /// only affect a single connection, they are dropped if no function is registered.
+    pub fn on_error<F>(&mut self, on_error: F)
+    where
+        F: Fn(io::Error) + Send + Sync + 'static,
+    {
+        self.on_error = Some(Arc::new(on_error));
+    }
+
+    /// returns a handle which can be used to stop the server from another thread
+    pub fn shutdown_handle(&self) -> io::Result<ShutdownHandle> {
+        Ok(ShutdownHandle {
+            addr: self.listener.local_addr()?,
+            shutdown: self.shutdown.clone(),
+        })
+    }
+
+    /// accepts and handles connections until `ShutdownHandle::shutdown` is called
+    ///
+    /// on shutdown the server stops accepting new connections, lets the workers finish the
+    /// requests they are currently handling as well as the connections still waiting in the
+    /// queue and returns once all workers have exited.
+    pub fn run(self) -> io::Result<()> {
+        let (sender, receiver) = mpsc::sync_channel::<TcpStream>(self.queue_len);
+        let receiver = Arc::new(Mutex::new(receiver));
+        let upgraded = Arc::new(AtomicUsize::new(0));
+
+        let workers = (0..self.workers.max(1))
+            .map(|_| {
+                let worker = Worker {
+                    receiver: receiver.clone(),
+                    handler: self.handler.clone(),
+                    max_request_len: self.max_request_len,
+                    keep_alive_timeout: self.keep_alive_timeout,
+                    read_timeout: self.read_timeout,
+                    max_upgraded: self.max_upgraded,
+                    upgraded: upgraded.clone(),
+                    on_error: self.on_error.clone(),
+                    shutdown: self.shutdown.clone(),
+                };
+                thread::spawn(move || worker.run())
+            })
+            .collect::<Vec<_>>();
+
+        let result = loop {
+            let connection = match self.listener.accept() {
+                Ok((connection, _)) => connection,
+                Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                Err(_) if self.shutdown.load(Ordering::SeqCst) => break Ok(()),
+                Err(err) => break Err(err),
+            };
+
+            if self.shutdown.load(Ordering::SeqCst) {
+                break Ok(());
+            }
+
+            match sender.try_send(connection) {
+                Ok(()) => (),
+                Err(TrySendError::Full(mut connection)) => {
+                    let _ = connection.write_all(&service_unavailable());
+                    let _ = connection.shutdown(Shutdown::Both);
+                }
+                Err(TrySendError::Disconnected(_)) => {
+                    break Err(io::Error::other("all server workers exited"));
+                }
+            }
+        };
+
+        // dropping the sender lets the workers exit once the queue is drained
+        drop(sender);
+
+        for worker in workers {
+            if worker.join().is_err() {
+                return Err(io::Error::other("server worker panicked"));
+            }
+        }
+
+        result
+    }
+}
+
+/// handle to stop a running `Server`
+#[derive(Debug, Clone)]
+pub struct ShutdownHandle {
+    addr: SocketAddr,
+    shutdown: Arc<AtomicBool>,
+}
+
+impl ShutdownHandle {
+    /// asks the server to shut down gracefully
+    ///
+    /// this function does not wait for the server to exit, `Server::run` returns once all
+    /// in-flight requests have been answered.
+    pub fn shutdown(&self) {
+        if self.shutdown.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        // wake up the accept loop which is blocked until the next connection comes in
+        let _ = TcpStream::connect_timeout(&self.addr, Duration::from_secs(1));
+    }
+
+    pub fn is_shutdown(&self) -> bool {
+        self.shutdown.load(Ordering::SeqCst)
+    }
+}
+
+struct Worker {
+    receiver: Arc<Mutex<Receiver<TcpStream>>>,
+    handler: BoxedHandler,
+    max_request_len: usize,
+    keep_alive_timeout: Duration,
+    read_timeout: Duration,
+    max_upgraded: usize,
+    /// number of upgraded connections currently served, shared by all workers
+    upgraded: Arc<AtomicUsize>,
+    on_error: Option<OnError>,
+    shutdown: Arc<AtomicBool>,
+}
+
+impl Worker {
+    fn run(self) {
+        loop {
+            // the guard is dropped at the end of the statement so other workers can receive
+            // while this one handles the connection
+            let connection = self.receiver.lock().unwrap().recv();
+
+            let Ok(connection) = connection else {
+                return;
+            };
+
+            // a panicking handler only loses its connection, not the worker
+            let result =
+                panic::catch_unwind(AssertUnwindSafe(|| self.handle_connection(connection)))
+                    .unwrap_or_else(|payload| Err(panic_error(payload.as_ref())));
+
+            if let Err(err) = result {
+                self.report(err);
+            }
+        }
+    }
+
+    /// passes `err` to `Server::on_error`
+    fn report(&self, err: io::Error) {
+        if let Some(on_error) = &self.on_error {
+            on_error(err);
+        }
+    }
+
+    fn handle_connection(&self, mut connection: TcpStream) -> io::Result<()> {
+        connection.set_read_timeout(Some(POLL_INTERVAL))?;
+
+        let mut buf = Vec::new();
+        let interim_stream = Arc::new(connection.try_clone()?);
+
+        loop {
+            let (answer, len) = match self.read_request(&mut connection, &mut buf)? {
+                Some(ReadResult::Request(len)) => {
+                    (self.respond(&buf[..len], &interim_stream)?, len)
+                }
+                Some(ReadResult::Reject(mut response)) => {
+                    // the rest of the request is never read, so the connection can not be reused
+                    response
+                        .header_mut()
+                        .insert("Connection".to_owned(), "close".to_owned());
+                    connection.write_all(&response.finish())?;
+                    break;
+                }
+                None => break,
+            };
+
+            let Answer {
+                response,
+                keep_alive,
+                on_upgrade,
+            } = answer;
+
+            response.send(&mut connection)?;
+            connection.flush()?;
+
+            buf.drain(..len);
+
+            if let Some((on_upgrade, slot)) = on_upgrade {
+                // the connection no longer speaks HTTP, whatever follows the request belongs to
+                // the new protocol
+                connection.set_read_timeout(None)?;
+                let upgraded = Upgraded::new(connection, buf);
+                thread::spawn(move || {
+                    let _slot = slot;
+                    on_upgrade(upgraded)
+                });
+                return Ok(());
+            }
+
+            if !keep_alive || self.shutdown.load(Ordering::SeqCst) {
+                break;
+            }
+        }
+
+        let _ = connection.shutdown(Shutdown::Both);
+
+        Ok(())
+    }
+
+    /// lets the handler answer the request `raw_request`
+    fn respond(&self, raw_request: &[u8], interim_stream: &Arc<TcpStream>) -> io::Result<Answer> {
+        let (request, _) = Request::parse_strict(raw_request).map_err(io::Error::from)?;
+
+        // handlers get the body without its chunked framing
+        let decoded = request.decode_chunked().map_err(io::Error::from)?;
+        let mut request = match &decoded {
+            Some((body, trailers)) => request.with_body(body, trailers),
+            None => request,
+        };
+        // the handler consumes the request, the copy still borrows `raw_request`
+        let head = request.clone();
+        let interim = Interim::new(interim_stream.clone(), request.version());
+        let upgrade = Upgrade::new();
+        request.extensions_mut().insert(interim);
+        request.extensions_mut().insert(upgrade.clone());
+
+        // so is an error, the client only learns that something went wrong
+        let response = self.handler.call(request).map_err(|err| self.report(err));
+
+        // a response which can not be sent unchanged is a bug in the handler
+        let mut response = response
+            .ok()
+            .filter(|response| response.validate().is_ok())
+            .unwrap_or_else(ResponseBuilder::internal_server_error);
+
+        // so is switching protocols without an upgrade which was requested and accepted
+        let on_upgrade = upgrade.take();
+        let switching = response.status() == Status::SwitchingProtocols;
+        if switching && (on_upgrade.is_none() || head.upgrade().is_none()) {
+            response = ResponseBuilder::internal_server_error();
+        }
+        let switching = response.status() == Status::SwitchingProtocols;
+
+        // the registered function writes the body of an event stream, which ends when the
+        // connection is closed
+        let event_stream = sse::is_event_stream(&response);
+        let on_upgrade = on_upgrade.filter(|_| switching || event_stream);
+
+        // each upgraded connection takes a thread, beyond the limit the upgrade is refused
+        let slot = on_upgrade
+            .as_ref()
+            .and_then(|_| UpgradeSlot::acquire(&self.upgraded, self.max_upgraded));
+        if on_upgrade.is_some() && slot.is_none() {
+            response = ResponseBuilder::service_unavailable();
+        }
+        let on_upgrade = on_upgrade.zip(slot);
+        let switching = response.status() == Status::SwitchingProtocols;
+        let event_stream = sse::is_event_stream(&response);
+
+        // responses to HEAD never carry a body, whatever the handler produced
+        *response.omit_body_mut() |= head.method() == Method::Head;
+
+        let keep_alive = should_keep_alive(&head, &response) && !event_stream;
+        if !switching {
+            announce_connection(&mut response, head.version(), keep_alive);
+        }
+
+        Ok(Answer {
+            response,
+            keep_alive,
+            on_upgrade,
+        })
+    }
+
+    /// answers a request whose body has not arrived yet, see `Handler::check_continue`
+    ///
+    /// returns `None` if the body should be read
+    fn check_head(&self, request: &Request, head_len: usize) -> Option<ResponseBuilder> {
+        let too_large = request
+            .header()
+            .get("Content-Length")
+            .and_then(|len| len.parse::<usize>().ok())
+            .is_some_and(|len| head_len.saturating_add(len) > self.max_request_len);
+
+        if too_large {
+            return Some(ResponseBuilder::payload_too_large());
+        }
+
+        if request.expects_continue() {
+            return self.handler.check_continue(request);
+        }
+
+        // the only expectation defined is 100-continue, see RFC 9110 section 10.1.1
+        if request.version() != Version::Http0 && request.header().contains("Expect") {
+            return Some(ResponseBuilder::expectation_failed());
+        }
+
+        None
+    }
+
+    /// reads from `connection` until `buf` holds a full request
+    ///
+    /// returns `None` if the connection should be closed without a response, that is when the
+    /// client closed it, the keep-alive timeout expired or the server is shutting down before
+    /// any byte of the next request arrived
+    fn read_request(
+        &self,
+        connection: &mut TcpStream,
+        buf: &mut Vec<u8>,
+    ) -> io::Result<Option<ReadResult>> {
+        let mut chunk = [0_u8; 4096];
+        // when the connection became idle or the request last received bytes
+        let mut last_read = Instant::now();
+        let mut head_checked = false;
+
+        loop {
+            match Request::parse_strict(buf) {
+                Ok((_, len)) => return Ok(Some(ReadResult::Request(len))),
+                Err(Error::NotEnoughData) if buf.len() >= self.max_request_len => {
+                    return Ok(Some(ReadResult::Reject(ResponseBuilder::bad_request())));
+                }
+                Err(Error::NotEnoughData) if !head_checked => {
+                    if let Ok((request, head_len)) = Request::parse_head_strict(buf) {
+                        head_checked = true;
+
+                        if let Some(response) = self.check_head(&request, head_len) {
+                            return Ok(Some(ReadResult::Reject(response)));
+                        }
+
+                        if request.expects_continue() {
+                            connection
+                                .write_all(&ResponseBuilder::new(Status::Continue).finish())?;
+                        }
+                    }
+                }
+                Err(Error::NotEnoughData) => (),
+                Err(_) => return Ok(Some(ReadResult::Reject(ResponseBuilder::bad_request()))),
+            }
+
+            match connection.read(&mut chunk) {
+                Ok(0) => return Ok(None),
+                Ok(n) => {
+                    buf.extend_from_slice(&chunk[..n]);
+                    last_read = Instant::now();
+                }
+                Err(err) if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                    // a started request is read to the end as long as bytes keep arriving, only
+                    // idle connections are closed on shutdown
+                    if !buf.is_empty() {
+                        if last_read.elapsed() >= self.read_timeout {
+                            let response = ResponseBuilder::new(Status::RequestTimeout);
+                            return Ok(Some(ReadResult::Reject(response)));
+                        }
+                    } else if self.shutdown.load(Ordering::SeqCst)
+                        || last_read.elapsed() >= self.keep_alive_timeout
+                    {
+                        return Ok(None);
+                    }
+                }
+                Err(err) if err.kind() == ErrorKind::Interrupted => (),
+                Err(err) => return Err(err),
+            }
+        }
+    }
+}
+
+enum ReadResult {
+    /// `buf` starts with a full request of the given length
+    Request(usize),
+    /// the request is answered with this response before it was read completely, eg: because
+    /// it is malformed or too large
+    Reject(ResponseBuilder),
+}
+
+/// the response to a request and what happens to the connection afterwards
+struct Answer {
+    response: ResponseBuilder,
+    keep_alive: bool,
+    /// takes over the connection after the response was sent, see `Upgrade`
+    on_upgrade: Option<(OnUpgrade, UpgradeSlot)>,
+}
+
+/// one of the `Server::max_upgraded` upgraded connections, released when dropped
+struct UpgradeSlot(Arc<AtomicUsize>);
+
+impl UpgradeSlot {
+    /// takes a slot unless `max` upgraded connections are served already
+    fn acquire(upgraded: &Arc<AtomicUsize>, max: usize) -> Option<Self> {
+        upgraded
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| {
+                (n < max).then_some(n + 1)
+            })
+            .ok()
+            .map(|_| Self(upgraded.clone()))
+    }
+}
+
+impl Drop for UpgradeSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// turns the payload of a caught panic into the error reported to `Server::on_error`
+fn panic_error(payload: &(dyn Any + Send)) -> io::Error {
+    let message = payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown panic");
+
+    io::Error::other(format!("connection handler panicked: {message}"))
+}
+
+/// tells the client whether the connection stays open, HTTP/1.0 clients only keep it open if
+/// the response says so
+fn announce_connection(response: &mut ResponseBuilder, version: Version, keep_alive: bool) {
+    let option = match (keep_alive, version) {
+        (false, _) => "close",
+        (true, Version::Http0) => "keep-alive",
+        (true, _) => return,
+    };
+
+    let field = response
+        .header_mut()
+        .iter_mut()
+        .find(|(key, _)| key.eq_ignore_ascii_case("Connection"));
+
+    match field {
+        Some((_, value)) if connection::has_token(value, option) => (),
+        Some((_, value)) => {
+            value.push_str(", ");
+            value.push_str(option);
+        }
+        None => {
+            response
+                .header_mut()
+                .insert("Connection".to_owned(), option.to_owned());
+        }
+    }
+}
+
+fn service_unavailable() -> Vec<u8> {
+    let mut response = ResponseBuilder::service_unavailable();
+    response
+        .header_mut()
+        .insert("Connection".to_owned(), "close".to_owned());
+    response.finish()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn handler(request: Request) -> io::Result<ResponseBuilder> {
+        match (request.method(), request.uri()) {
+            (Method::Get | Method::Head, "/") => {
+                let mut response = ResponseBuilder::ok();
+                response.body_mut().extend_from_slice(b"Hello World");
+                Ok(response)
+            }
+            (Method::Post, "/echo") => {
+                let mut response = ResponseBuilder::ok();
+                response.body_mut().extend_from_slice(request.body());
+                if let Some(digest) = request.trailers().get("Digest") {
+                    response
+                        .header_mut()
+                        .insert("X-Digest".to_owned(), digest.to_owned());
+                }
+                Ok(response)
+            }
+            (Method::Get, "/hints") => {
+                let interim = request.extensions().get::<Interim>().unwrap();
+                let mut hints = ResponseBuilder::early_hints();
+                hints
+                    .header_mut()
+                    .insert("Link".to_owned(), "</a.css>; rel=preload".to_owned());
+                interim.send(&hints)?;
+                interim.send(&hints)?;
+                assert!(interim.send(&ResponseBuilder::ok()).is_err());
+                Ok(ResponseBuilder::ok())
+            }
+            (Method::Get, "/inject") => {
+                let mut response = ResponseBuilder::ok();
+                response
+                    .header_mut()
+                    .insert("X-Echo".to_owned(), "a\r\nSet-Cookie: b".to_owned());
+                Ok(response)
+            }
+            (Method::Get, "/upgrade") => {
+                let upgrade = request.extensions().get::<Upgrade>().unwrap();
+                // echoes everything after the request until the client shuts down its side
+                upgrade.on_upgrade(|mut upgraded| {
+                    let mut stream = upgraded.stream().try_clone().unwrap();
+                    io::copy(&mut upgraded, &mut stream).unwrap();
+                });
+                Ok(ResponseBuilder::switching_protocols("echo"))
+            }
+            (Method::Get, "/panic") => panic!("handler failed"),
+            (Method::Get, "/fail") => Err(io::Error::other("handler failed")),
+            (Method::Get, "/no-upgrade") => Ok(ResponseBuilder::switching_protocols("echo")),
+            (Method::Get, "/truncated") => {
+                let path =
+                    std::env::temp_dir().join(format!("reqse-truncated-{}", std::process::id()));
+                let file = std::fs::File::options()
+                    .read(true)
+                    .write(true)
+                    .create(true)
+                    .truncate(true)
+                    .open(&path)?;
+                let _ = std::fs::remove_file(path);
+
+                // the file is empty, so sending the body fails
+                let mut response = ResponseBuilder::ok();
+                *response.file_mut() = Some(crate::FileRange::new(file, 0, 100));
+                Ok(response)
+            }
+            _ => Ok(ResponseBuilder::not_found()),
+        }
+    }
+
+    fn read_to_end(mut connection: TcpStream) -> String {
+        let mut buf = String::new();
+        connection.read_to_string(&mut buf).unwrap();
+        buf
+    }
+
+    #[test]
+    fn test_serve_keep_alive_and_shutdown() {
+        let server = Server::bind("127.0.0.1:0", handler).unwrap();
+        let addr = server.local_addr().unwrap();
+        let handle = server.shutdown_handle().unwrap();
+        let server = thread::spawn(move || server.run());
+
+        let mut connection = TcpStream::connect(addr).unwrap();
+        connection
+            .write_all(b"POST /echo HTTP/1.1\r\nContent-Length: 4\r\n\r\nping")
+            .unwrap();
+        connection.write_all(b"HEAD / HTTP/1.1\r\n\r\n").unwrap();
+        connection
+            .write_all(b"GET / HTTP/1.1\r\nConnection: close\r\n\r\n")
+            .unwrap();
+
+        let response = read_to_end(connection);
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains("\r\n\r\nping"));
+        assert!(response.ends_with("\r\n\r\nHello World"));
+        // the HEAD response announces the body without sending it
+        assert_eq!(response.matches("Content-Length: 11\r\n").count(), 2);
+        assert_eq!(response.matches("Hello World").count(), 1);
+
+        handle.shutdown();
+        server.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_chunked_request() {
+        let server = Server::bind("127.0.0.1:0", handler).unwrap();
+        let addr = server.local_addr().unwrap();
+        let handle = server.shutdown_handle().unwrap();
+        let server = thread::spawn(move || server.run());
+
+        let mut connection = TcpStream::connect(addr).unwrap();
+        connection
+            .write_all(
+                b"POST /echo HTTP/1.1\r\nTransfer-Encoding: chunked\r\nConnection: close\r\n\r\n\
+                5\r\nHello\r\n6\r\n World\r\n0\r\nDigest: x\r\n\r\n",
+            )
+            .unwrap();
+
+        // the handler sees the decoded body and the trailers
+        let response = read_to_end(connection);
+        assert!(response.contains("X-Digest: x\r\n"), "{}", response);
+        assert!(response.ends_with("\r\n\r\nHello World"), "{}", response);
+
+        handle.shutdown();
+        server.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_empty_body_has_length() {
+        let server = Server::bind("127.0.0.1:0", handler).unwrap();
+        let addr = server.local_addr().unwrap().to_string();
+        let handle = server.shutdown_handle().unwrap();
+        let server = thread::spawn(move || server.run());
+
+        // the response ends with its head, so the client does not wait for the connection to
+        // be closed by the keep-alive timeout
+        let pool = crate::client::ClientPool::new();
+        let start = Instant::now();
+        let response = pool
+            .send(&addr, crate::RequestBuilder::get("/missing".to_owned()))
+            .unwrap();
+        assert_eq!(response.status(), Status::NotFound);
+        assert_eq!(response.header().get("Content-Length"), Some("0"));
+        assert!(start.elapsed() < Duration::from_secs(1));
+        assert_eq!(pool.idle_connections(&addr), 1);
+
+        handle.shutdown();
+        server.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_serve_http0_keep_alive() {
+        let server = Server::bind("127.0.0.1:0", handler).unwrap();
+        let addr = server.local_addr().unwrap();
+        let handle = server.shutdown_handle().unwrap();
+        let server = thread::spawn(move || server.run());
+
+        let mut connection = TcpStream::connect(addr).unwrap();
+        connection
+            .write_all(b"GET / HTTP/1.0\r\nConnection: keep-alive\r\n\r\n")
+            .unwrap();
+        connection.write_all(b"GET / HTTP/1.0\r\n\r\n").unwrap();
+
+        let response = read_to_end(connection);
+        assert_eq!(response.matches("Hello World").count(), 2);
+        assert_eq!(
+            response.matches("\r\nConnection: keep-alive\r\n").count(),
+            1
+        );
+        assert_eq!(response.matches("\r\nConnection: close\r\n").count(), 1);
+
+        handle.shutdown();
+        server.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_upgrade() {
+        let server = Server::bind("127.0.0.1:0", handler).unwrap();
+        let addr = server.local_addr().unwrap();
+        let handle = server.shutdown_handle().unwrap();
+        let server = thread::spawn(move || server.run());
+
+        let mut connection = TcpStream::connect(addr).unwrap();
+        connection
+            .write_all(
+                b"GET /upgrade HTTP/1.1\r\nConnection: upgrade\r\nUpgrade: echo\r\n\r\nearly",
+            )
+            .unwrap();
+
+        let switching = "HTTP/1.1 101 Switching Protocols\r\n";
+        let mut buf = vec![0; switching.len()];
+        connection.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, switching.as_bytes());
+
+        connection.write_all(b" late").unwrap();
+        connection.shutdown(Shutdown::Write).unwrap();
+
+        let response = read_to_end(connection);
+        assert!(response.contains("Upgrade: echo\r\n"));
+        assert!(response.ends_with("\r\n\r\nearly late"));
+
+        // switching protocols without upgrade requested and accepted is a bug
+        for raw_request in [
+            "GET /upgrade HTTP/1.1\r\nConnection: close\r\n\r\n",
+            "GET /no-upgrade HTTP/1.1\r\nConnection: upgrade, close\r\nUpgrade: echo\r\n\r\n",
+        ] {
+            let mut connection = TcpStream::connect(addr).unwrap();
+            connection.write_all(raw_request.as_bytes()).unwrap();
+            let response = read_to_end(connection);
+            assert!(response.starts_with("HTTP/1.1 500 Internal Server Error\r\n"));
+        }
+
+        handle.shutdown();
+        server.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_max_upgraded() {
+        let mut server = Server::bind("127.0.0.1:0", handler).unwrap();
+        *server.max_upgraded_mut() = 1;
+        let addr = server.local_addr().unwrap();
+        let handle = server.shutdown_handle().unwrap();
+        let server = thread::spawn(move || server.run());
+
+        let upgrade = |addr| {
+            let mut connection = TcpStream::connect(addr).unwrap();
+            connection
+                .write_all(b"GET /upgrade HTTP/1.1\r\nConnection: upgrade\r\nUpgrade: echo\r\n\r\n")
+                .unwrap();
+            let mut buf = vec![0; 12];
+            connection.read_exact(&mut buf).unwrap();
+            (connection, String::from_utf8(buf).unwrap())
+        };
+
+        let (first, status) = upgrade(addr);
+        assert_eq!(status, "HTTP/1.1 101");
+
+        let (_, status) = upgrade(addr);
+        assert_eq!(status, "HTTP/1.1 503");
+
+        // the slot is free again once the first upgraded connection ended
+        first.shutdown(Shutdown::Write).unwrap();
+        read_to_end(first);
+        let upgraded = (0..50).any(|_| {
+            let (connection, status) = upgrade(addr);
+            let _ = connection.shutdown(Shutdown::Write);
+            thread::sleep(Duration::from_millis(10));
+            status == "HTTP/1.1 101"
+        });
+        assert!(upgraded);
+
+        handle.shutdown();
+        server.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_expect_continue() {
+        let mut server = Server::bind("127.0.0.1:0", handler).unwrap();
+        *server.max_request_len_mut() = 1024;
+        let addr = server.local_addr().unwrap();
+        let handle = server.shutdown_handle().unwrap();
+        let server = thread::spawn(move || server.run());
+
+        let mut connection = TcpStream::connect(addr).unwrap();
+        connection
+            .write_all(b"POST /echo HTTP/1.1\r\nContent-Length: 4\r\nExpect: 100-continue\r\n\r\n")
+            .unwrap();
+
+        let mut interim = [0; 25];
+        connection.read_exact(&mut interim).unwrap();
+        assert_eq!(&interim, b"HTTP/1.1 100 Continue\r\n\r\n");
+
+        connection.write_all(b"ping").unwrap();
+        connection
+            .write_all(
+                b"POST /echo HTTP/1.1\r\nContent-Length: 4096\r\nExpect: 100-continue\r\n\r\n",
+            )
+            .unwrap();
+
+        let response = read_to_end(connection);
+        assert!(response.contains("\r\n\r\npingHTTP/1.1 413 Payload Too Large\r\n"));
+        assert!(response.contains("\r\nConnection: close\r\n"));
+
+        let mut connection = TcpStream::connect(addr).unwrap();
+        connection
+            .write_all(b"POST /echo HTTP/1.1\r\nContent-Length: 4\r\nExpect: magic\r\n\r\n")
+            .unwrap();
+        assert!(read_to_end(connection).starts_with("HTTP/1.1 417 Expectation Failed\r\n"));
+
+        handle.shutdown();
+        server.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_interim_responses() {
+        let server = Server::bind("127.0.0.1:0", handler).unwrap();
+        let addr = server.local_addr().unwrap();
+        let handle = server.shutdown_handle().unwrap();
+        let server = thread::spawn(move || server.run());
+
+        let hints = "HTTP/1.1 103 Early Hints\r\nLink: </a.css>; rel=preload\r\n\r\n";
+
+        let mut connection = TcpStream::connect(addr).unwrap();
+        connection
+            .write_all(b"GET /hints HTTP/1.1\r\nConnection: close\r\n\r\n")
+            .unwrap();
+        let response = read_to_end(connection);
+        assert_eq!(
+            response,
+            format!(
+                "{0}{0}HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: 0\r\n\r\n",
+                hints
+            )
+        );
+
+        let mut connection = TcpStream::connect(addr).unwrap();
+        connection
+            .write_all(b"GET /hints HTTP/1.0\r\n\r\n")
+            .unwrap();
+        let response = read_to_end(connection);
+        assert_eq!(
+            response,
+            "HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: 0\r\n\r\n"
+        );
+
+        handle.shutdown();
+        server.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_invalid_response_header() {
+        let server = Server::bind("127.0.0.1:0", handler).unwrap();
+        let addr = server.local_addr().unwrap();
+        let handle = server.shutdown_handle().unwrap();
+        let server = thread::spawn(move || server.run());
+
+        let mut connection = TcpStream::connect(addr).unwrap();
+        connection
+            .write_all(b"GET /inject HTTP/1.1\r\nConnection: close\r\n\r\n")
+            .unwrap();
+
+        let response = read_to_end(connection);
+        assert_eq!(
+            response,
+            "HTTP/1.1 500 Internal Server Error\r\nConnection: close\r\nContent-Length: 0\r\n\r\n"
+        );
+
+        handle.shutdown();
+        server.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_on_error() {
+        let (sender, receiver) = mpsc::channel();
+        let sender = Mutex::new(sender);
+
+        let mut server = Server::bind("127.0.0.1:0", handler).unwrap();
+        server.on_error(move |err| sender.lock().unwrap().send(err.kind()).unwrap());
+        let addr = server.local_addr().unwrap();
+        let handle = server.shutdown_handle().unwrap();
+        let server = thread::spawn(move || server.run());
+
+        let mut connection = TcpStream::connect(addr).unwrap();
+        connection
+            .write_all(b"GET /truncated HTTP/1.1\r\n\r\n")
+            .unwrap();
+
+        let kind = receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(kind, ErrorKind::UnexpectedEof);
+
+        // errors returned by the handler are reported before answering with 500
+        let mut connection = TcpStream::connect(addr).unwrap();
+        connection
+            .write_all(b"GET /fail HTTP/1.1\r\nConnection: close\r\n\r\n")
+            .unwrap();
+        assert!(read_to_end(connection).starts_with("HTTP/1.1 500 Internal Server Error\r\n"));
+
+        let kind = receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(kind, ErrorKind::Other);
+
+        handle.shutdown();
+        server.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_worker_survives_panic() {
+        let (sender, receiver) = mpsc::channel();
+        let sender = Mutex::new(sender);
+
+        let mut server = Server::bind("127.0.0.1:0", handler).unwrap();
+        *server.workers_mut() = 1;
+        server.on_error(move |err| sender.lock().unwrap().send(err.to_string()).unwrap());
+        let addr = server.local_addr().unwrap();
+        let handle = server.shutdown_handle().unwrap();
+        let server = thread::spawn(move || server.run());
+
+        let mut connection = TcpStream::connect(addr).unwrap();
+        connection
+            .write_all(b"GET /panic HTTP/1.1\r\n\r\n")
+            .unwrap();
+        assert_eq!(read_to_end(connection), "");
+
+        let message = receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert!(message.contains("handler failed"), "{}", message);
+
+        // the only worker still answers requests
+        let mut connection = TcpStream::connect(addr).unwrap();
+        connection
+            .write_all(b"GET / HTTP/1.1\r\nConnection: close\r\n\r\n")
+            .unwrap();
+        assert!(read_to_end(connection).ends_with("Hello World"));
+
+        handle.shutdown();
+        server.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_slow_request() {
+        let mut server = Server::bind("127.0.0.1:0", handler).unwrap();
+        *server.keep_alive_timeout_mut() = Duration::from_millis(200);
+        *server.read_timeout_mut() = Duration::from_millis(400);
+        let addr = server.local_addr().unwrap();
+        let handle = server.shutdown_handle().unwrap();
+        let server = thread::spawn(move || server.run());
+
+        // a request which keeps sending is read even if it takes longer than the keep-alive
+        // timeout
+        let mut connection = TcpStream::connect(addr).unwrap();
+        connection
+            .write_all(b"POST /echo HTTP/1.1\r\nConnection: close\r\nContent-Length: 4\r\n\r\n")
+            .unwrap();
+        for byte in b"ping" {
+            thread::sleep(Duration::from_millis(100));
+            connection.write_all(&[*byte]).unwrap();
+        }
+        assert!(read_to_end(connection).ends_with("\r\n\r\nping"));
+
+        // one which stalls is answered with 408
+        let mut connection = TcpStream::connect(addr).unwrap();
+        connection
+            .write_all(b"POST /echo HTTP/1.1\r\nContent-Length: 4\r\n\r\npi")
+            .unwrap();
+        let response = read_to_end(connection);
+        assert!(
+            response.starts_with("HTTP/1.1 408 Request Timeout\r\n"),
+            "{}",
+            response
+        );
+
+        handle.shutdown();
+        server.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_service_unavailable_when_queue_is_full() {
+        let mut server = Server::bind("127.0.0.1:0", handler).unwrap();
+        *server.workers_mut() = 1;
+        *server.queue_len_mut() = 1;
+        let addr = server.local_addr().unwrap();
+        let handle = server.shutdown_handle().unwrap();
+        let server = thread::spawn(move || server.run());
+
+        // occupies the only worker until the request is completed
+        let mut busy = TcpStream::connect(addr).unwrap();
+        busy.write_all(b"GET / HTTP/1.1\r\n").unwrap();
+        thread::sleep(Duration::from_millis(200));
+
+        // takes the only place in the queue
+        let mut queued = TcpStream::connect(addr).unwrap();
+        thread::sleep(Duration::from_millis(200));
+
+        let rejected = TcpStream::connect(addr).unwrap();
+        assert!(read_to_end(rejected).starts_with("HTTP/1.1 503 Service Unavailable\r\n"));
+
+        // shutdown waits for the in-flight request and drains the queue
+        handle.shutdown();
+        busy.write_all(b"\r\n").unwrap();
+        assert!(read_to_end(busy).starts_with("HTTP/1.1 200 OK\r\n"));
+
+        queued.write_all(b"GET /missing HTTP/1.1\r\n\r\n").unwrap();
+        assert!(read_to_end(queued).starts_with("HTTP/1.1 404 Not Found\r\n"));
+
+        server.join().unwrap().unwrap();
+    }
+}
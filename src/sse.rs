@@ -0,0 +1,640 @@
+//! Server-Sent Events, see the `text/event-stream` format in the HTML standard
+//!
+//! a handler answers with `ResponseBuilder::event_stream`, takes over the connection with
+//! `Upgrade` and writes events to it with `EventStream`. The stream ends when the connection is
+//! closed. Clients read events with `Client::event_stream` and `EventReader`.
+//!
+//! # Examples
+//! ```no_run
+//! use reqse::{Request, ResponseBuilder, Upgrade, sse::{Event, EventStream}};
+//! use std::{io, thread, time::Duration};
+//!
+//! fn ticks(request: Request) -> io::Result<ResponseBuilder> {
+//!     // a reconnecting client continues after the last event it received
+//!     let start = request
+//!         .header()
+//!         .get("Last-Event-ID")
+//!         .and_then(|id| id.parse::<u64>().ok())
+//!         .map_or(0, |id| id + 1);
+//!
+//!     let upgrade = request.extensions().get::<Upgrade>().unwrap();
+//!     upgrade.on_upgrade(move |upgraded| {
+//!         let mut events = EventStream::new(upgraded);
+//!         for i in start.. {
+//!             let mut event = Event::new(format!("tick {i}"));
+//!             *event.id_mut() = Some(i.to_string());
+//!             if events.send(&event).is_err() {
+//!                 break;
+//!             }
+//!             thread::sleep(Duration::from_secs(1));
+//!         }
+//!     });
+//!
+//!     Ok(ResponseBuilder::event_stream())
+//! }
+//! ```
+
+use std::{
+    io::{self, Read, Write},
+    time::Duration,
+};
+
+use crate::{Error, ResponseBuilder, Status};
+
+/// the media type of an event stream
+pub(crate) const CONTENT_TYPE: &str = "text/event-stream";
+
+/// whether the `Content-Type` value `content_type` is `text/event-stream`, parameters are
+/// ignored
+pub(crate) fn is_content_type(content_type: &str) -> bool {
+    content_type
+        .split(';')
+        .next()
+        .is_some_and(|media_type| media_type.trim().eq_ignore_ascii_case(CONTENT_TYPE))
+}
+
+/// whether `response` starts an event stream whose body is written after the head, see
+/// `ResponseBuilder::event_stream`
+pub(crate) fn is_event_stream(response: &ResponseBuilder) -> bool {
+    let content_type = response
+        .header()
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case("Content-Type"))
+        .is_some_and(|(_, value)| is_content_type(value));
+
+    content_type
+        && response.status() == Status::Ok
+        && response.body().is_empty()
+        && response.file().is_none()
+        && response.trailers().is_empty()
+        && !response.omit_body()
+}
+
+/// a single event
+///
+/// # Examples
+/// ```
+/// use reqse::sse::Event;
+///
+/// let mut event = Event::new("first line\nsecond line".to_owned());
+/// *event.event_mut() = Some("update".to_owned());
+/// *event.id_mut() = Some("42".to_owned());
+///
+/// assert_eq!(
+///     event.encode(),
+///     b"event: update\nid: 42\ndata: first line\ndata: second line\n\n"
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Event {
+    event: Option<String>,
+    id: Option<String>,
+    retry: Option<Duration>,
+    data: String,
+}
+
+impl Event {
+    pub fn new(data: String) -> Self {
+        Self {
+            data,
+            ..Self::default()
+        }
+    }
+
+    /// the event type, clients treat events without one as `message`
+    pub fn event(&self) -> Option<&str> {
+        self.event.as_deref()
+    }
+
+    pub fn event_mut(&mut self) -> &mut Option<String> {
+        &mut self.event
+    }
+
+    /// the id a reconnecting client sends back in `Last-Event-ID`
+    ///
+    /// events read by `EventReader` carry the last id received so far, even if the event itself
+    /// had none
+    pub fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    pub fn id_mut(&mut self) -> &mut Option<String> {
+        &mut self.id
+    }
+
+    /// how long a client waits before reconnecting after the connection was lost
+    ///
+    /// always `None` for events read by `EventReader`, which keeps it in `EventReader::retry`
+    pub fn retry(&self) -> Option<Duration> {
+        self.retry
+    }
+
+    pub fn retry_mut(&mut self) -> &mut Option<Duration> {
+        &mut self.retry
+    }
+
+    /// the payload, it may span multiple lines
+    pub fn data(&self) -> &str {
+        &self.data
+    }
+
+    pub fn data_mut(&mut self) -> &mut String {
+        &mut self.data
+    }
+
+    /// checks that the event can be encoded without changing its meaning
+    ///
+    /// # Error
+    /// - `Error::InvalidHeader` if the event type or id contain a line break, or the id a NUL
+    ///   character, which makes clients ignore it
+    pub fn validate(&self) -> Result<(), Error> {
+        let line_break = |value: &str| value.contains(['\r', '\n']);
+
+        if self.event.as_deref().is_some_and(line_break)
+            || self
+                .id
+                .as_deref()
+                .is_some_and(|id| line_break(id) || id.contains('\0'))
+        {
+            return Err(Error::InvalidHeader);
+        }
+
+        Ok(())
+    }
+
+    /// encodes the event without validating it, see `validate`
+    ///
+    /// every line of `data` becomes a `data` field, whatever line break separated it
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(self.data.len() + 16);
+
+        if let Some(event) = &self.event {
+            push_field(&mut buf, "event", event);
+        }
+        if let Some(id) = &self.id {
+            push_field(&mut buf, "id", id);
+        }
+        if let Some(retry) = self.retry {
+            push_field(&mut buf, "retry", &retry.as_millis().to_string());
+        }
+        for line in lines(&self.data) {
+            push_field(&mut buf, "data", line);
+        }
+
+        // the empty line dispatches the event
+        buf.push(b'\n');
+
+        buf
+    }
+}
+
+/// splits `text` at CRLF, CR and LF, the line breaks of an event stream
+fn lines(text: &str) -> impl Iterator<Item = &str> {
+    text.split("\r\n").flat_map(|line| line.split(['\r', '\n']))
+}
+
+fn push_field(buf: &mut Vec<u8>, name: &str, value: &str) {
+    buf.extend_from_slice(name.as_bytes());
+    if !value.is_empty() {
+        buf.extend_from_slice(b": ");
+        buf.extend_from_slice(value.as_bytes());
+    }
+    buf.push(b'\n');
+}
+
+/// writes events to the body of an event stream
+///
+/// every write is flushed right away, so events are not held back in a buffer of `stream`.
+///
+/// # Examples
+/// ```
+/// use reqse::sse::{Event, EventStream};
+/// use std::time::Duration;
+///
+/// let mut events = EventStream::new(Vec::new());
+/// events.retry(Duration::from_secs(3)).unwrap();
+/// events.send(&Event::new("Hello".to_owned())).unwrap();
+/// events.keep_alive().unwrap();
+///
+/// assert_eq!(events.into_inner(), b"retry: 3000\n\ndata: Hello\n\n:\n");
+/// ```
+#[derive(Debug)]
+pub struct EventStream<W> {
+    stream: W,
+}
+
+impl<W: Write> EventStream<W> {
+    /// `stream` has to be positioned at the start of the body, see `ResponseBuilder::event_stream`
+    pub fn new(stream: W) -> Self {
+        Self { stream }
+    }
+
+    pub fn stream(&self) -> &W {
+        &self.stream
+    }
+
+    pub fn stream_mut(&mut self) -> &mut W {
+        &mut self.stream
+    }
+
+    pub fn into_inner(self) -> W {
+        self.stream
+    }
+
+    /// sends `event` after validating it, see `Event::validate`
+    pub fn send(&mut self, event: &Event) -> io::Result<()> {
+        event.validate()?;
+        self.write(&event.encode())
+    }
+
+    /// sends a comment, clients ignore it
+    pub fn comment(&mut self, text: &str) -> io::Result<()> {
+        let mut buf = Vec::with_capacity(text.len() + 3);
+        for line in lines(text) {
+            buf.push(b':');
+            if !line.is_empty() {
+                buf.push(b' ');
+                buf.extend_from_slice(line.as_bytes());
+            }
+            buf.push(b'\n');
+        }
+        self.write(&buf)
+    }
+
+    /// sends an empty comment, which keeps proxies from closing an idle connection and lets the
+    /// server notice a client which went away
+    pub fn keep_alive(&mut self) -> io::Result<()> {
+        self.comment("")
+    }
+
+    /// tells the client how long to wait before reconnecting, without dispatching an event
+    pub fn retry(&mut self, retry: Duration) -> io::Result<()> {
+        let mut buf = Vec::new();
+        push_field(&mut buf, "retry", &retry.as_millis().to_string());
+        buf.push(b'\n');
+        self.write(&buf)
+    }
+
+    fn write(&mut self, buf: &[u8]) -> io::Result<()> {
+        self.stream.write_all(buf)?;
+        self.stream.flush()
+    }
+}
+
+/// reads events from the body of an event stream
+///
+/// the reader keeps the last event id and the reconnection time the server sent. A client
+/// reconnecting after the connection was lost sends the id in `Last-Event-ID` so the server
+/// can continue where the stream broke off, `Client::event_stream` does that when the
+/// `last_event_id` of the previous reader is put into the new request.
+///
+/// # Examples
+/// ```
+/// use reqse::sse::EventReader;
+///
+/// let body = b"event: update\ndata: first\ndata: second\nid: 1\n\n: comment\ndata: last\n\n";
+/// let mut events = EventReader::new(&body[..]);
+///
+/// let event = events.read().unwrap().unwrap();
+/// assert_eq!(event.event(), Some("update"));
+/// assert_eq!(event.data(), "first\nsecond");
+/// assert_eq!(event.id(), Some("1"));
+///
+/// let event = events.read().unwrap().unwrap();
+/// assert_eq!(event.event(), None);
+/// assert_eq!(event.data(), "last");
+/// assert_eq!(event.id(), Some("1"));
+///
+/// assert_eq!(events.read().unwrap(), None);
+/// assert_eq!(events.last_event_id(), "1");
+/// ```
+#[derive(Debug)]
+pub struct EventReader<R> {
+    reader: R,
+    /// bytes read from `reader` which do not form a complete line yet
+    buf: Vec<u8>,
+    eof: bool,
+    bom_checked: bool,
+    last_event_id: String,
+    retry: Option<Duration>,
+    max_event_len: usize,
+    /// the type, id and data of the event being read, the id only becomes the last event id
+    /// once the event is dispatched
+    event_type: String,
+    pending_id: Option<String>,
+    data: String,
+}
+
+impl<R: Read> EventReader<R> {
+    /// `reader` has to be positioned at the start of the body
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            buf: Vec::new(),
+            eof: false,
+            bom_checked: false,
+            last_event_id: String::new(),
+            retry: None,
+            max_event_len: 1024 * 1024,
+            event_type: String::new(),
+            pending_id: None,
+            data: String::new(),
+        }
+    }
+
+    pub fn reader(&self) -> &R {
+        &self.reader
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// the id of the last event received, empty if there was none
+    ///
+    /// it is set before reading the first event from the `Last-Event-ID` the stream was
+    /// requested with, see `Client::event_stream`
+    pub fn last_event_id(&self) -> &str {
+        &self.last_event_id
+    }
+
+    pub fn last_event_id_mut(&mut self) -> &mut String {
+        &mut self.last_event_id
+    }
+
+    /// the reconnection time the server sent last
+    pub fn retry(&self) -> Option<Duration> {
+        self.retry
+    }
+
+    /// maximum number of bytes a single line or the data of a single event may occupy, a
+    /// stream exceeding it fails with `io::ErrorKind::InvalidData`
+    pub fn max_event_len(&self) -> usize {
+        self.max_event_len
+    }
+
+    pub fn max_event_len_mut(&mut self) -> &mut usize {
+        &mut self.max_event_len
+    }
+
+    /// reads the next event, returns `None` once the stream ended
+    ///
+    /// an event which is cut off by the end of the stream is dropped.
+    pub fn read(&mut self) -> io::Result<Option<Event>> {
+        while let Some(line) = self.read_line()? {
+            if let Some(event) = self.process_line(&line)? {
+                return Ok(Some(event));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// interprets a single line, returns the event an empty line dispatches
+    fn process_line(&mut self, line: &str) -> Result<Option<Event>, Error> {
+        if line.is_empty() {
+            return Ok(self.dispatch());
+        }
+
+        let (field, value) = match line.split_once(':') {
+            // a comment
+            Some(("", _)) => return Ok(None),
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+
+        match field {
+            "event" => value.clone_into(&mut self.event_type),
+            "data" => {
+                if self.data.len() + value.len() + 1 > self.max_event_len {
+                    return Err(Error::TooLarge);
+                }
+                self.data.push_str(value);
+                self.data.push('\n');
+            }
+            // an id with NUL is ignored, since clients could not send it back
+            "id" if !value.contains('\0') => self.pending_id = Some(value.to_owned()),
+            "retry" if !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit()) => {
+                if let Ok(millis) = value.parse() {
+                    self.retry = Some(Duration::from_millis(millis));
+                }
+            }
+            // unknown fields are ignored
+            _ => (),
+        }
+
+        Ok(None)
+    }
+
+    /// finishes the event being read, an event without data is not dispatched but still sets
+    /// the last event id
+    fn dispatch(&mut self) -> Option<Event> {
+        if let Some(id) = self.pending_id.take() {
+            self.last_event_id = id;
+        }
+
+        let event_type = std::mem::take(&mut self.event_type);
+        let mut data = std::mem::take(&mut self.data);
+
+        if data.is_empty() {
+            return None;
+        }
+
+        data.pop();
+
+        Some(Event {
+            event: Some(event_type).filter(|event_type| !event_type.is_empty()),
+            id: Some(self.last_event_id.clone()).filter(|id| !id.is_empty()),
+            retry: None,
+            data,
+        })
+    }
+
+    /// reads the next line without its line break, returns `None` at the end of the stream
+    fn read_line(&mut self) -> io::Result<Option<String>> {
+        loop {
+            // a stream may start with a byte order mark
+            if !self.bom_checked && (self.buf.len() >= 3 || self.eof) {
+                if self.buf.starts_with("\u{feff}".as_bytes()) {
+                    self.buf.drain(..3);
+                }
+                self.bom_checked = true;
+            }
+
+            let line_break = self.buf.iter().position(|b| matches!(b, b'\r' | b'\n'));
+
+            if let Some(end) = line_break.filter(|_| self.bom_checked) {
+                let len = match self.buf.get(end..end + 2) {
+                    Some(b"\r\n") => 2,
+                    // the LF of a CRLF may still be on its way
+                    None if self.buf[end] == b'\r' && !self.eof => {
+                        self.fill()?;
+                        continue;
+                    }
+                    _ => 1,
+                };
+
+                let line = String::from_utf8_lossy(&self.buf[..end]).into_owned();
+                self.buf.drain(..end + len);
+                return Ok(Some(line));
+            }
+
+            if self.eof {
+                return Ok(None);
+            }
+
+            if self.buf.len() >= self.max_event_len {
+                return Err(Error::TooLarge.into());
+            }
+
+            self.fill()?;
+        }
+    }
+
+    /// reads more bytes into `buf`, setting `eof` at the end of the stream
+    fn fill(&mut self) -> io::Result<()> {
+        let mut chunk = [0_u8; 4096];
+
+        loop {
+            match self.reader.read(&mut chunk) {
+                Ok(0) => {
+                    self.eof = true;
+                    return Ok(());
+                }
+                Ok(n) => {
+                    self.buf.extend_from_slice(&chunk[..n]);
+                    return Ok(());
+                }
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => (),
+                Err(err) => return Err(err),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{RequestBuilder, Server, Upgrade, client::Client};
+    use std::thread;
+
+    fn read_all(body: &[u8]) -> Vec<Event> {
+        let mut reader = EventReader::new(body);
+        let mut events = Vec::new();
+        while let Some(event) = reader.read().unwrap() {
+            events.push(event);
+        }
+        events
+    }
+
+    #[test]
+    fn test_encode() {
+        let mut event = Event::new("a\r\nb\rc\n\nd".to_owned());
+        *event.retry_mut() = Some(Duration::from_millis(1500));
+        assert_eq!(
+            event.encode(),
+            b"retry: 1500\ndata: a\ndata: b\ndata: c\ndata\ndata: d\n\n"
+        );
+        assert_eq!(read_all(&event.encode())[0].data(), "a\nb\nc\n\nd");
+
+        assert_eq!(Event::new(String::new()).encode(), b"data\n\n");
+        assert_eq!(read_all(b"data\n\n"), vec![Event::new(String::new())]);
+
+        let mut event = Event::new("x".to_owned());
+        *event.id_mut() = Some("1\nid: 2".to_owned());
+        assert_eq!(event.validate(), Err(Error::InvalidHeader));
+
+        let mut events = EventStream::new(Vec::new());
+        assert!(events.send(&event).is_err());
+        events.comment("a\nb").unwrap();
+        assert_eq!(events.into_inner(), b": a\n: b\n");
+    }
+
+    #[test]
+    fn test_read() {
+        let body = "\u{feff}data:no space\r\ndata:  two spaces\r\r\
+            event: x\nretry: 250\nretry: 1s\nid: 7\nid: a\0b\nfoo: bar\ndata\n\n\
+            event: dropped\n\ndata: cut off";
+        let events = read_all(body.as_bytes());
+
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].data(), "no space\n two spaces");
+        assert_eq!(events[0].event(), None);
+        assert_eq!(events[0].id(), None);
+        assert_eq!(events[1].data(), "");
+        assert_eq!(events[1].event(), Some("x"));
+        assert_eq!(events[1].id(), Some("7"));
+
+        let mut reader = EventReader::new(body.as_bytes());
+        while reader.read().unwrap().is_some() {}
+        assert_eq!(reader.retry(), Some(Duration::from_millis(250)));
+
+        // an empty id resets the last event id
+        let mut reader = EventReader::new(&b"id: 1\ndata: a\n\nid\ndata: b\n\n"[..]);
+        assert_eq!(reader.read().unwrap().unwrap().id(), Some("1"));
+        assert_eq!(reader.read().unwrap().unwrap().id(), None);
+
+        // the id of an event cut off by the end of the stream is not taken over
+        let mut reader = EventReader::new(&b"id: 1\n\ndata: x\nid: 5\n"[..]);
+        assert!(reader.read().unwrap().is_none());
+        assert_eq!(reader.last_event_id(), "1");
+
+        let mut reader = EventReader::new(&b"data: 0123456789\n\n"[..]);
+        *reader.max_event_len_mut() = 8;
+        let err = reader.read().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_event_stream() {
+        fn handler(request: crate::Request) -> io::Result<ResponseBuilder> {
+            let last_event_id = request
+                .header()
+                .get("Last-Event-ID")
+                .unwrap_or("0")
+                .parse::<u32>()
+                .unwrap();
+
+            let upgrade = request.extensions().get::<Upgrade>().unwrap();
+            upgrade.on_upgrade(move |upgraded| {
+                let mut events = EventStream::new(upgraded);
+                events.keep_alive().unwrap();
+                for id in last_event_id + 1..=last_event_id + 2 {
+                    let mut event = Event::new(format!("event {id}"));
+                    *event.id_mut() = Some(id.to_string());
+                    events.send(&event).unwrap();
+                }
+            });
+
+            Ok(ResponseBuilder::event_stream())
+        }
+
+        let server = Server::bind("127.0.0.1:0", handler).unwrap();
+        let addr = server.local_addr().unwrap().to_string();
+        let handle = server.shutdown_handle().unwrap();
+        let server = thread::spawn(move || server.run());
+
+        let client = Client::new();
+        let (mut events, response) = client
+            .event_stream(&addr, RequestBuilder::event_stream("/".to_owned()))
+            .unwrap();
+        assert_eq!(response.header().get("Connection"), Some("close"));
+
+        assert_eq!(events.read().unwrap().unwrap().data(), "event 1");
+        assert_eq!(events.read().unwrap().unwrap().data(), "event 2");
+        assert_eq!(events.read().unwrap(), None);
+
+        // reconnecting continues after the last event received
+        let mut request = RequestBuilder::event_stream("/".to_owned());
+        request.header_mut().insert(
+            "Last-Event-ID".to_owned(),
+            events.last_event_id().to_owned(),
+        );
+        let (mut events, _) = client.event_stream(&addr, request).unwrap();
+        assert_eq!(events.last_event_id(), "2");
+        assert_eq!(events.read().unwrap().unwrap().data(), "event 3");
+
+        handle.shutdown();
+        server.join().unwrap().unwrap();
+    }
+}
@@ -0,0 +1,694 @@
+//! serving files from a directory
+//!
+//! # Examples
+//! ```no_run
+//! use reqse::{Router, Server, static_files::StaticFiles};
+//!
+//! let mut router = Router::new();
+//! router.get("/assets/*path", StaticFiles::new("./public"));
+//!
+//! Server::bind("localhost:3000", router).unwrap().run().unwrap();
+//! ```
+
+use std::{
+    fmt::Write as _,
+    fs::{self, File, Metadata},
+    io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{FileRange, Handler, Method, Request, ResponseBuilder, Status, date};
+
+/// a `Handler` serving the files below a root directory
+///
+/// the file is looked up by the `path` parameter captured by a `Router` wildcard (eg:
+/// `/assets/*path`) or, if there is none, by the whole request path. The path is
+/// percent-decoded and normalised, requests for anything outside the root directory are
+/// answered with `403 Forbidden`.
+///
+/// responses carry a `Content-Type` guessed from the file extension, `Last-Modified` and an
+/// `ETag`. Requests whose `If-Match` or `If-Unmodified-Since` fails are answered with
+/// `412 Precondition Failed`, conditional requests (`If-None-Match`, `If-Modified-Since`) with
+/// `304 Not Modified` and single byte ranges (`Range`, `If-Range`) with `206 Partial Content`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StaticFiles {
+    root: PathBuf,
+    index: Option<String>,
+    listing: bool,
+}
+
+impl StaticFiles {
+    /// serves the files below `root`, `index.html` is served for directories and directory
+    /// listings are disabled
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            index: Some("index.html".to_owned()),
+            listing: false,
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// name of the file served when a directory is requested, `None` disables index files
+    pub fn index(&self) -> Option<&str> {
+        self.index.as_deref()
+    }
+
+    pub fn index_mut(&mut self) -> &mut Option<String> {
+        &mut self.index
+    }
+
+    /// whether a generated HTML listing is served for directories without index file
+    pub fn listing(&self) -> bool {
+        self.listing
+    }
+
+    pub fn listing_mut(&mut self) -> &mut bool {
+        &mut self.listing
+    }
+
+    /// answers `request` with the file at `path` (relative to the root directory)
+    pub fn serve(&self, request: &Request, path: &str) -> io::Result<ResponseBuilder> {
+        if !matches!(request.method(), Method::Get | Method::Head) {
+            let mut response = ResponseBuilder::new(Status::MethodNotAllowed);
+            response
+                .header_mut()
+                .insert("Allow".to_owned(), "GET, HEAD".to_owned());
+            return Ok(response);
+        }
+
+        let Some(segments) = normalize(path) else {
+            return Ok(ResponseBuilder::forbidden());
+        };
+
+        let mut file_path = self.root.clone();
+        file_path.extend(&segments);
+
+        // symlinks must not lead outside the root either
+        let (Ok(root), Ok(file_path)) = (self.root.canonicalize(), file_path.canonicalize()) else {
+            return Ok(ResponseBuilder::not_found());
+        };
+
+        if !file_path.starts_with(&root) {
+            return Ok(ResponseBuilder::forbidden());
+        }
+
+        let metadata = fs::metadata(&file_path)?;
+
+        if !metadata.is_dir() {
+            return self.serve_file(request, &file_path, &metadata);
+        }
+
+        // relative links in index files and listings only work with a trailing slash
+        if !request.path().ends_with('/') {
+            let mut response = ResponseBuilder::new(Status::MovedPermanently);
+            let query = request.uri().find('?').map(|i| &request.uri()[i..]);
+            response.header_mut().insert(
+                "Location".to_owned(),
+                format!("{}/{}", request.path(), query.unwrap_or("")),
+            );
+            return Ok(response);
+        }
+
+        if let Some(index) = &self.index {
+            let index = file_path.join(index);
+            if let Ok(metadata) = fs::metadata(&index)
+                && metadata.is_file()
+            {
+                return self.serve_file(request, &index, &metadata);
+            }
+        }
+
+        if self.listing {
+            return listing(request.path(), &file_path);
+        }
+
+        Ok(ResponseBuilder::not_found())
+    }
+
+    fn serve_file(
+        &self,
+        request: &Request,
+        path: &Path,
+        metadata: &Metadata,
+    ) -> io::Result<ResponseBuilder> {
+        let len = metadata.len();
+        let modified = metadata.modified().ok();
+        let etag = etag(len, modified);
+        let last_modified = modified.map(date::format_http_date);
+
+        let mut response = ResponseBuilder::ok();
+        let header = response.header_mut();
+        header.insert("ETag".to_owned(), etag.clone());
+        header.insert("Accept-Ranges".to_owned(), "bytes".to_owned());
+        if let Some(last_modified) = &last_modified {
+            header.insert("Last-Modified".to_owned(), last_modified.clone());
+        }
+
+        match precondition(request, &etag, modified) {
+            Precondition::Pass => (),
+            Precondition::NotModified => {
+                *response.status_mut() = Status::NotModified;
+                return Ok(response);
+            }
+            Precondition::Failed => return Ok(ResponseBuilder::new(Status::PreconditionFailed)),
+        }
+
+        response
+            .header_mut()
+            .insert("Content-Type".to_owned(), mime_type(path).to_owned());
+
+        let range = match request.header().get("Range") {
+            Some(range) if if_range(request, &etag, modified) => parse_range(range, len),
+            _ => Range::Full,
+        };
+
+        let (start, end) = match range {
+            Range::Full => (0, len),
+            Range::Partial(start, end) => {
+                *response.status_mut() = Status::PartialContent;
+                response.header_mut().insert(
+                    "Content-Range".to_owned(),
+                    format!("bytes {}-{}/{}", start, end - 1, len),
+                );
+                (start, end)
+            }
+            Range::Unsatisfiable => {
+                let mut response = ResponseBuilder::new(Status::RangeNotSatisfiable);
+                response
+                    .header_mut()
+                    .insert("Content-Range".to_owned(), format!("bytes */{}", len));
+                return Ok(response);
+            }
+        };
+
+        // the file is sent by `ResponseBuilder::send` without reading it into memory
+        *response.omit_body_mut() = request.method() == Method::Head;
+        *response.file_mut() = Some(FileRange::new(File::open(path)?, start, end - start));
+
+        Ok(response)
+    }
+}
+
+impl Handler for StaticFiles {
+    fn call(&self, request: Request) -> io::Result<ResponseBuilder> {
+        let path = request.param("path").unwrap_or(request.path());
+        self.serve(&request, path)
+    }
+}
+
+/// guesses the `Content-Type` from the extension of `path`
+///
+/// # Examples
+/// ```
+/// use reqse::static_files::mime_type;
+///
+/// assert_eq!(mime_type("style.css".as_ref()), "text/css; charset=utf-8");
+/// assert_eq!(mime_type("archive.unknown".as_ref()), "application/octet-stream");
+/// ```
+pub fn mime_type(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or("")
+        .to_ascii_lowercase();
+
+    match extension.as_str() {
+        "html" | "htm" => "text/html; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "js" | "mjs" => "text/javascript; charset=utf-8",
+        "json" => "application/json",
+        "map" => "application/json",
+        "txt" | "md" => "text/plain; charset=utf-8",
+        "csv" => "text/csv; charset=utf-8",
+        "xml" => "application/xml",
+        "wasm" => "application/wasm",
+        "pdf" => "application/pdf",
+        "zip" => "application/zip",
+        "gz" => "application/gzip",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "svg" => "image/svg+xml",
+        "ico" => "image/x-icon",
+        "webp" => "image/webp",
+        "avif" => "image/avif",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "ttf" => "font/ttf",
+        "otf" => "font/otf",
+        "mp3" => "audio/mpeg",
+        "ogg" => "audio/ogg",
+        "wav" => "audio/wav",
+        "mp4" => "video/mp4",
+        "webm" => "video/webm",
+        _ => "application/octet-stream",
+    }
+}
+
+/// percent-decodes `path` and splits it into segments, `.` segments are dropped and `..`
+/// segments remove the previous one
+///
+/// returns `None` if the path leaves the root or a segment contains characters which could be
+/// interpreted as path separators
+fn normalize(path: &str) -> Option<Vec<String>> {
+    let path = percent_decode(path)?;
+    let mut segments: Vec<String> = Vec::new();
+
+    for segment in path.split('/') {
+        match segment {
+            "" | "." => (),
+            ".." => {
+                segments.pop()?;
+            }
+            _ if segment.contains(['\\', '\0', ':']) => return None,
+            _ => segments.push(segment.to_owned()),
+        }
+    }
+
+    Some(segments)
+}
+
+fn percent_decode(s: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(s.len());
+    let mut iter = s.bytes();
+
+    while let Some(b) = iter.next() {
+        if b == b'%' {
+            let hex = [iter.next()?, iter.next()?];
+            let hex = std::str::from_utf8(&hex).ok()?;
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+        } else {
+            bytes.push(b);
+        }
+    }
+
+    String::from_utf8(bytes).ok()
+}
+
+fn etag(len: u64, modified: Option<SystemTime>) -> String {
+    let modified = modified
+        .and_then(|m| m.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+
+    format!("\"{:x}-{:x}\"", len, modified)
+}
+
+/// the outcome of the conditional request fields
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Precondition {
+    Pass,
+    /// the client's cached copy is still valid
+    NotModified,
+    /// the client only wants the request performed on another version of the file
+    Failed,
+}
+
+/// evaluates the conditional request fields in the order of RFC 9110 section 13.2.2
+fn precondition(request: &Request, etag: &str, modified: Option<SystemTime>) -> Precondition {
+    let header = request.header();
+    // an unknown modification date or an invalid date leaves the field without effect
+    let modified_since = |date: &str| {
+        let date = date::parse_http_date(date)?;
+        modified.map(|modified| truncate_secs(modified) > date)
+    };
+
+    if let Some(if_match) = header.get("If-Match") {
+        // strong comparison, weak tags never match
+        if !if_match
+            .split(',')
+            .any(|tag| tag.trim() == "*" || tag.trim() == etag)
+        {
+            return Precondition::Failed;
+        }
+    } else if let Some(since) = header.get("If-Unmodified-Since")
+        && modified_since(since) == Some(true)
+    {
+        return Precondition::Failed;
+    }
+
+    if let Some(if_none_match) = header.get("If-None-Match") {
+        // weak comparison, W/ prefixes are ignored
+        let matched = if_none_match.split(',').any(|tag| {
+            let tag = tag.trim();
+            tag == "*" || tag.trim_start_matches("W/") == etag
+        });
+
+        return match (matched, request.method()) {
+            (false, _) => Precondition::Pass,
+            (true, Method::Get | Method::Head) => Precondition::NotModified,
+            (true, _) => Precondition::Failed,
+        };
+    }
+
+    let get = matches!(request.method(), Method::Get | Method::Head);
+    match header.get("If-Modified-Since") {
+        Some(since) if get && modified_since(since) == Some(false) => Precondition::NotModified,
+        _ => Precondition::Pass,
+    }
+}
+
+/// whether the `Range` header applies, see RFC 9110 section 13.1.5
+fn if_range(request: &Request, etag: &str, modified: Option<SystemTime>) -> bool {
+    let Some(if_range) = request.header().get("If-Range") else {
+        return true;
+    };
+
+    if if_range.starts_with('"') {
+        return if_range == etag;
+    }
+
+    match (date::parse_http_date(if_range), modified) {
+        (Some(date), Some(modified)) => truncate_secs(modified) == date,
+        _ => false,
+    }
+}
+
+/// HTTP dates have a resolution of one second
+fn truncate_secs(time: SystemTime) -> SystemTime {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    UNIX_EPOCH + std::time::Duration::from_secs(secs)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Range {
+    Full,
+    /// start inclusive, end exclusive
+    Partial(u64, u64),
+    Unsatisfiable,
+}
+
+/// parses a `Range` header for a file of `len` bytes, only a single byte range is supported,
+/// other ranges are ignored and the full file is served
+fn parse_range(range: &str, len: u64) -> Range {
+    let Some(range) = range.trim().strip_prefix("bytes=") else {
+        return Range::Full;
+    };
+
+    if range.contains(',') {
+        return Range::Full;
+    }
+
+    let Some((start, end)) = range.trim().split_once('-') else {
+        return Range::Full;
+    };
+
+    let number = |s: &str| -> Option<u64> {
+        if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        s.parse().ok()
+    };
+
+    match (start, end) {
+        ("", suffix) => match number(suffix) {
+            Some(0) => Range::Unsatisfiable,
+            Some(_) if len == 0 => Range::Unsatisfiable,
+            Some(suffix) => Range::Partial(len.saturating_sub(suffix), len),
+            None => Range::Full,
+        },
+        (start, "") => match number(start) {
+            Some(start) if start >= len => Range::Unsatisfiable,
+            Some(start) => Range::Partial(start, len),
+            None => Range::Full,
+        },
+        (start, end) => match (number(start), number(end)) {
+            (Some(start), Some(end)) if start > end => Range::Full,
+            (Some(start), Some(_)) if start >= len => Range::Unsatisfiable,
+            (Some(start), Some(end)) => Range::Partial(start, end.min(len - 1) + 1),
+            _ => Range::Full,
+        },
+    }
+}
+
+/// generates an HTML page listing the entries of the directory `dir`
+fn listing(request_path: &str, dir: &Path) -> io::Result<ResponseBuilder> {
+    let mut entries = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            let is_dir = entry.file_type().ok()?.is_dir();
+            Some((name, is_dir))
+        })
+        .collect::<Vec<_>>();
+
+    entries.sort();
+
+    let title = escape_html(request_path);
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>Index of {0}</title></head>\n<body>\n<h1>Index of {0}</h1>\n<ul>\n",
+        title
+    );
+
+    if request_path != "/" {
+        html.push_str("<li><a href=\"../\">../</a></li>\n");
+    }
+
+    for (name, is_dir) in entries {
+        let slash = if is_dir { "/" } else { "" };
+        let _ = writeln!(
+            html,
+            "<li><a href=\"{}{}\">{}{}</a></li>",
+            percent_encode(&name),
+            slash,
+            escape_html(&name),
+            slash
+        );
+    }
+
+    html.push_str("</ul>\n</body>\n</html>\n");
+
+    let mut response = ResponseBuilder::ok();
+    response.header_mut().insert(
+        "Content-Type".to_owned(),
+        "text/html; charset=utf-8".to_owned(),
+    );
+    response.body_mut().extend_from_slice(html.as_bytes());
+
+    Ok(response)
+}
+
+fn escape_html(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+fn percent_encode(s: &str) -> String {
+    let mut encoded = String::with_capacity(s.len());
+
+    for b in s.bytes() {
+        if b.is_ascii_alphanumeric() || b"-._~".contains(&b) {
+            encoded.push(b as char);
+        } else {
+            let _ = write!(encoded, "%{:02X}", b);
+        }
+    }
+
+    encoded
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Router;
+
+    /// creates a fresh directory with a few files below the temp directory
+    fn root(name: &str) -> PathBuf {
+        let root =
+            std::env::temp_dir().join(format!("reqse-static-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("docs")).unwrap();
+        fs::create_dir_all(root.join("empty")).unwrap();
+        fs::write(root.join("hello.txt"), "Hello World").unwrap();
+        fs::write(root.join("docs/index.html"), "<h1>docs</h1>").unwrap();
+        root
+    }
+
+    fn get(handler: &dyn Handler, raw_request: &str) -> ResponseBuilder {
+        let request = Request::from_bytes(raw_request.as_bytes()).unwrap();
+        handler.call(request).unwrap()
+    }
+
+    /// the serialized body, including the file range
+    fn body(response: &ResponseBuilder) -> Vec<u8> {
+        let mut raw = Vec::new();
+        response.write_to(&mut raw).unwrap();
+        let i = raw.windows(4).position(|w| w == b"\r\n\r\n").unwrap();
+        raw[i + 4..].to_vec()
+    }
+
+    #[test]
+    fn test_serve_file() {
+        let files = StaticFiles::new(root("file"));
+
+        let response = get(&files, "GET /hello.txt HTTP/1.1\r\n\r\n");
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(body(&response), b"Hello World");
+        assert_eq!(
+            response.header()["Content-Type"],
+            "text/plain; charset=utf-8"
+        );
+        assert!(response.header().contains_key("Last-Modified"));
+
+        let response = get(&files, "GET /docs/ HTTP/1.1\r\n\r\n");
+        assert_eq!(body(&response), b"<h1>docs</h1>");
+
+        let response = get(&files, "GET /docs?x=1 HTTP/1.1\r\n\r\n");
+        assert_eq!(response.status(), Status::MovedPermanently);
+        assert_eq!(response.header()["Location"], "/docs/?x=1");
+
+        let response = get(&files, "GET /missing HTTP/1.1\r\n\r\n");
+        assert_eq!(response.status(), Status::NotFound);
+    }
+
+    #[test]
+    fn test_traversal() {
+        let files = StaticFiles::new(root("traversal"));
+
+        for path in [
+            "/../etc/passwd",
+            "/docs/../../x",
+            "/%2e%2e/x",
+            "/a%5c..%5cx",
+        ] {
+            let response = get(&files, &format!("GET {} HTTP/1.1\r\n\r\n", path));
+            assert_eq!(response.status(), Status::Forbidden, "{}", path);
+        }
+
+        let response = get(&files, "GET /docs/../hello.txt HTTP/1.1\r\n\r\n");
+        assert_eq!(response.status(), Status::Ok);
+    }
+
+    #[test]
+    fn test_conditional() {
+        let files = StaticFiles::new(root("conditional"));
+
+        let response = get(&files, "GET /hello.txt HTTP/1.1\r\n\r\n");
+        let etag = response.header()["ETag"].clone();
+        let last_modified = response.header()["Last-Modified"].clone();
+
+        let raw_request = format!("GET /hello.txt HTTP/1.1\r\nIf-None-Match: {}\r\n\r\n", etag);
+        let response = get(&files, &raw_request);
+        assert_eq!(response.status(), Status::NotModified);
+        assert!(body(&response).is_empty());
+
+        let raw_request = format!(
+            "GET /hello.txt HTTP/1.1\r\nIf-Modified-Since: {}\r\n\r\n",
+            last_modified
+        );
+        assert_eq!(get(&files, &raw_request).status(), Status::NotModified);
+
+        let raw_request = "GET /hello.txt HTTP/1.1\r\nIf-None-Match: \"other\"\r\n\r\n";
+        assert_eq!(get(&files, raw_request).status(), Status::Ok);
+
+        // If-Match and If-Unmodified-Since are evaluated first
+        let raw_request = format!("GET /hello.txt HTTP/1.1\r\nIf-Match: {}\r\n\r\n", etag);
+        assert_eq!(get(&files, &raw_request).status(), Status::Ok);
+
+        let raw_request = format!(
+            "GET /hello.txt HTTP/1.1\r\nIf-Match: \"other\", W/{}\r\nIf-None-Match: {}\r\n\r\n",
+            etag, etag
+        );
+        let response = get(&files, &raw_request);
+        assert_eq!(response.status(), Status::PreconditionFailed);
+        assert!(response.file().is_none());
+
+        let raw_request = format!(
+            "GET /hello.txt HTTP/1.1\r\nIf-Unmodified-Since: {}\r\n\r\n",
+            last_modified
+        );
+        assert_eq!(get(&files, &raw_request).status(), Status::Ok);
+
+        let raw_request =
+            "GET /hello.txt HTTP/1.1\r\nIf-Unmodified-Since: Sun, 06 Nov 1994 08:49:37 GMT\r\n\r\n";
+        assert_eq!(
+            get(&files, raw_request).status(),
+            Status::PreconditionFailed
+        );
+
+        // If-Unmodified-Since is ignored if If-Match is present
+        let raw_request = "GET /hello.txt HTTP/1.1\r\nIf-Match: *\r\n\
+            If-Unmodified-Since: Sun, 06 Nov 1994 08:49:37 GMT\r\n\r\n";
+        assert_eq!(get(&files, raw_request).status(), Status::Ok);
+    }
+
+    #[test]
+    fn test_range() {
+        let files = StaticFiles::new(root("range"));
+
+        let response = get(&files, "GET /hello.txt HTTP/1.1\r\nRange: bytes=6-\r\n\r\n");
+        assert_eq!(response.status(), Status::PartialContent);
+        assert_eq!(response.header()["Content-Range"], "bytes 6-10/11");
+        assert_eq!(body(&response), b"World");
+
+        let response = get(&files, "GET /hello.txt HTTP/1.1\r\nRange: bytes=-5\r\n\r\n");
+        assert_eq!(body(&response), b"World");
+
+        let response = get(
+            &files,
+            "GET /hello.txt HTTP/1.1\r\nRange: bytes=0-4\r\n\r\n",
+        );
+        assert_eq!(body(&response), b"Hello");
+
+        let response = get(
+            &files,
+            "GET /hello.txt HTTP/1.1\r\nRange: bytes=20-\r\n\r\n",
+        );
+        assert_eq!(response.status(), Status::RangeNotSatisfiable);
+        assert_eq!(response.header()["Content-Range"], "bytes */11");
+
+        let raw_request =
+            "GET /hello.txt HTTP/1.1\r\nRange: bytes=0-4\r\nIf-Range: \"old\"\r\n\r\n";
+        let response = get(&files, raw_request);
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(body(&response), b"Hello World");
+    }
+
+    #[test]
+    fn test_listing_and_router() {
+        let mut files = StaticFiles::new(root("listing"));
+        *files.listing_mut() = true;
+
+        let mut router = Router::new();
+        router.get("/static/*path", files);
+
+        let response = get(&router, "GET /static/ HTTP/1.1\r\n\r\n");
+        let html = String::from_utf8(body(&response)).unwrap();
+        assert!(html.contains("<a href=\"hello.txt\">hello.txt</a>"));
+        assert!(html.contains("<a href=\"docs/\">docs/</a>"));
+
+        let response = get(&router, "HEAD /static/hello.txt HTTP/1.1\r\n\r\n");
+        assert!(body(&response).is_empty());
+        assert!(
+            response
+                .clone()
+                .finish()
+                .starts_with(b"HTTP/1.1 200 OK\r\n")
+        );
+        assert_eq!(response.file().map(FileRange::len), Some(11));
+
+        let response = get(&router, "GET /static/empty/ HTTP/1.1\r\n\r\n");
+        assert_eq!(response.status(), Status::Ok);
+    }
+}
@@ -4,11 +4,27 @@ use crate::Error;
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum Status {
+    // 1xx informational
+    Continue,
+    SwitchingProtocols,
+    Processing,
+    EarlyHints,
+
     // 2xx success codes
     Ok,
+    Created,
+    Accepted,
+    NoContent,
+    PartialContent,
 
     // 3xx redirection
     MultipleChoices,
+    MovedPermanently,
+    Found,
+    SeeOther,
+    NotModified,
+    TemporaryRedirect,
+    PermanentRedirect,
 
     // 4xx client error
     BadRequest,
@@ -16,54 +32,187 @@ pub enum Status {
     Forbidden,
     NotFound,
     MethodNotAllowed,
+    RequestTimeout,
+    Conflict,
+    Gone,
+    LengthRequired,
+    PayloadTooLarge,
+    UriTooLong,
+    UnsupportedMediaType,
+    RangeNotSatisfiable,
+    PreconditionFailed,
+    ExpectationFailed,
     IamATeapot,
+    UnprocessableEntity,
+    TooManyRequests,
 
     // 5xx server error
     InternalServerError,
     NotImplemented,
+    BadGateway,
     ServiceUnavailable,
+    GatewayTimeout,
     HttpVersionNotSupported,
 }
 
 impl FromStr for Status {
     type Err = Error;
 
+    /// parses the status code and reason phrase of a status line (eg: `200 OK`), the reason
+    /// phrase is optional and not checked against the code
+    ///
+    /// a code between 100 and 599 without a variant is treated as the x00 code of its class, as
+    /// RFC 9110 section 15 asks recipients to, eg: `451` becomes `Status::BadRequest`
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (num, _) = s.trim().split_once(" ").ok_or(Error::InvalidHeader)?;
-
-        match num {
-            "200" => return Ok(Self::Ok),
-            "300" => return Ok(Self::MultipleChoices),
-            "400" => return Ok(Self::BadRequest),
-            "401" => return Ok(Self::Unauthorized),
-            "403" => return Ok(Self::Forbidden),
-            "404" => return Ok(Self::NotFound),
-            "405" => return Ok(Self::MethodNotAllowed),
-            "418" => return Ok(Self::IamATeapot),
-            "500" => return Ok(Self::InternalServerError),
-            "503" => return Ok(Self::ServiceUnavailable),
-            "505" => return Ok(Self::HttpVersionNotSupported),
-            _ => (),
+        let s = s.trim();
+        let num = s.split_once(" ").map(|(num, _)| num).unwrap_or(s);
+
+        if num.len() != 3 || !num.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(Error::InvalidHeader);
         }
 
-        todo!()
+        let code = num.parse().ok().ok_or(Error::InvalidHeader)?;
+
+        Self::from_code(code)
+            .or_else(|| Self::from_code(code / 100 * 100))
+            .ok_or(Error::InvalidHeader)
     }
 }
 
 impl Status {
+    /// returns the status matching the numeric status `code`
+    ///
+    /// # Examples
+    /// ```
+    /// use reqse::Status;
+    ///
+    /// assert_eq!(Status::from_code(404), Some(Status::NotFound));
+    /// assert_eq!(Status::from_code(999), None);
+    /// ```
+    pub fn from_code(code: u16) -> Option<Self> {
+        let status = match code {
+            100 => Self::Continue,
+            101 => Self::SwitchingProtocols,
+            102 => Self::Processing,
+            103 => Self::EarlyHints,
+            200 => Self::Ok,
+            201 => Self::Created,
+            202 => Self::Accepted,
+            204 => Self::NoContent,
+            206 => Self::PartialContent,
+            300 => Self::MultipleChoices,
+            301 => Self::MovedPermanently,
+            302 => Self::Found,
+            303 => Self::SeeOther,
+            304 => Self::NotModified,
+            307 => Self::TemporaryRedirect,
+            308 => Self::PermanentRedirect,
+            400 => Self::BadRequest,
+            401 => Self::Unauthorized,
+            403 => Self::Forbidden,
+            404 => Self::NotFound,
+            405 => Self::MethodNotAllowed,
+            408 => Self::RequestTimeout,
+            409 => Self::Conflict,
+            410 => Self::Gone,
+            411 => Self::LengthRequired,
+            412 => Self::PreconditionFailed,
+            413 => Self::PayloadTooLarge,
+            414 => Self::UriTooLong,
+            415 => Self::UnsupportedMediaType,
+            416 => Self::RangeNotSatisfiable,
+            417 => Self::ExpectationFailed,
+            418 => Self::IamATeapot,
+            422 => Self::UnprocessableEntity,
+            429 => Self::TooManyRequests,
+            500 => Self::InternalServerError,
+            501 => Self::NotImplemented,
+            502 => Self::BadGateway,
+            503 => Self::ServiceUnavailable,
+            504 => Self::GatewayTimeout,
+            505 => Self::HttpVersionNotSupported,
+            _ => return None,
+        };
+
+        Some(status)
+    }
+
+    /// returns the numeric status code
+    ///
+    /// # Examples
+    /// ```
+    /// use reqse::Status;
+    ///
+    /// assert_eq!(Status::NotFound.code(), 404);
+    /// ```
+    pub fn code(&self) -> u16 {
+        self.to_static_str()[..3].parse().unwrap()
+    }
+
+    /// whether this is the status of an interim response, which is followed by another response
+    /// to the same request
+    ///
+    /// `101 Switching Protocols` is informational but final, the connection speaks another
+    /// protocol after it
+    ///
+    /// # Examples
+    /// ```
+    /// use reqse::Status;
+    ///
+    /// assert!(Status::EarlyHints.is_interim());
+    /// assert!(!Status::SwitchingProtocols.is_interim());
+    /// assert!(!Status::Ok.is_interim());
+    /// ```
+    pub fn is_interim(&self) -> bool {
+        self.is_informational() && *self != Status::SwitchingProtocols
+    }
+
+    /// whether this is a 1xx status, responses with such a status never have a body
+    pub fn is_informational(&self) -> bool {
+        (100..200).contains(&self.code())
+    }
+
     pub fn to_static_str(&self) -> &'static str {
         match self {
+            Status::Continue => "100 Continue",
+            Status::SwitchingProtocols => "101 Switching Protocols",
+            Status::Processing => "102 Processing",
+            Status::EarlyHints => "103 Early Hints",
             Status::Ok => "200 OK",
+            Status::Created => "201 Created",
+            Status::Accepted => "202 Accepted",
+            Status::NoContent => "204 No Content",
+            Status::PartialContent => "206 Partial Content",
             Status::MultipleChoices => "300 Multiple Choices",
+            Status::MovedPermanently => "301 Moved Permanently",
+            Status::Found => "302 Found",
+            Status::SeeOther => "303 See Other",
+            Status::NotModified => "304 Not Modified",
+            Status::TemporaryRedirect => "307 Temporary Redirect",
+            Status::PermanentRedirect => "308 Permanent Redirect",
             Status::BadRequest => "400 Bad Request",
             Status::Unauthorized => "401 Unauthorized",
             Status::Forbidden => "403 Forbidden",
             Status::NotFound => "404 Not Found",
             Status::MethodNotAllowed => "405 Method Not Allowed",
+            Status::RequestTimeout => "408 Request Timeout",
+            Status::Conflict => "409 Conflict",
+            Status::Gone => "410 Gone",
+            Status::LengthRequired => "411 Length Required",
+            Status::PreconditionFailed => "412 Precondition Failed",
+            Status::PayloadTooLarge => "413 Payload Too Large",
+            Status::UriTooLong => "414 URI Too Long",
+            Status::UnsupportedMediaType => "415 Unsupported Media Type",
+            Status::RangeNotSatisfiable => "416 Range Not Satisfiable",
+            Status::ExpectationFailed => "417 Expectation Failed",
             Status::IamATeapot => "418 Im a teapot",
+            Status::UnprocessableEntity => "422 Unprocessable Entity",
+            Status::TooManyRequests => "429 Too Many Requests",
             Status::InternalServerError => "500 Internal Server Error",
             Status::NotImplemented => "501 Not Implemented",
+            Status::BadGateway => "502 Bad Gateway",
             Status::ServiceUnavailable => "503 Service Unavailable",
+            Status::GatewayTimeout => "504 Gateway Timeout",
             Status::HttpVersionNotSupported => "505 HTTP Version Not Supported",
         }
     }
@@ -74,3 +223,23 @@ impl Display for Status {
         write!(f, "{}", self.to_static_str())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!("200 OK".parse(), Ok(Status::Ok));
+        assert_eq!("103 Early Hints".parse(), Ok(Status::EarlyHints));
+        assert_eq!("404".parse(), Ok(Status::NotFound));
+        assert_eq!("501 Not Implemented".parse(), Ok(Status::NotImplemented));
+        assert_eq!("299 Whatever".parse(), Ok(Status::Ok));
+        assert_eq!("199".parse(), Ok(Status::Continue));
+        assert_eq!("451 Unavailable".parse(), Ok(Status::BadRequest));
+        assert_eq!("599".parse(), Ok(Status::InternalServerError));
+        assert_eq!("099 Low".parse::<Status>(), Err(Error::InvalidHeader));
+        assert_eq!("600 High".parse::<Status>(), Err(Error::InvalidHeader));
+        assert_eq!("+20 OK".parse::<Status>(), Err(Error::InvalidHeader));
+    }
+}
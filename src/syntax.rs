@@ -0,0 +1,56 @@
+//! character classes of the HTTP grammar, see RFC 9110 section 5
+
+use std::collections::HashMap;
+
+use crate::Error;
+
+/// `tchar`, the characters allowed in methods and field names
+pub(crate) fn is_tchar(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b)
+}
+
+/// `token`, one or more `tchar`
+pub(crate) fn is_token(s: &str) -> bool {
+    !s.is_empty() && s.bytes().all(is_tchar)
+}
+
+/// whether `b` may appear in a field value, that is anything but control characters except
+/// horizontal tab
+pub(crate) fn is_field_vchar(b: u8) -> bool {
+    b == b'\t' || (b >= 0x20 && b != 0x7f)
+}
+
+/// whether `s` may be sent as a field value
+pub(crate) fn is_field_value(s: &str) -> bool {
+    s.bytes().all(is_field_vchar)
+}
+
+/// whether `s` may be sent as request target, that is a non-empty string of visible ASCII
+/// characters, anything else has to be percent-encoded
+pub(crate) fn is_request_target(s: &str) -> bool {
+    !s.is_empty() && s.bytes().all(|b| b.is_ascii_graphic())
+}
+
+/// checks the fields of a builder before they are serialized
+pub(crate) fn validate_header(header: &HashMap<String, String>) -> Result<(), Error> {
+    let valid = header
+        .iter()
+        .all(|(key, value)| is_token(key) && is_field_value(value));
+
+    if !valid {
+        return Err(Error::InvalidHeader);
+    }
+
+    Ok(())
+}
+
+/// whether `buf` contains a CR not followed by LF or a LF not preceded by CR
+///
+/// a CR at the very end of `buf` is not bare yet, its LF may still arrive
+pub(crate) fn has_bare_cr_or_lf(buf: &[u8]) -> bool {
+    buf.iter().enumerate().any(|(i, b)| match b {
+        b'\r' => buf.get(i + 1).is_some_and(|next| *next != b'\n'),
+        b'\n' => i == 0 || buf[i - 1] != b'\r',
+        _ => false,
+    })
+}
@@ -0,0 +1,294 @@
+//! helpers for reading requests from and writing responses to tokio streams
+//!
+//! only available with the `tokio` feature enabled.
+//!
+//! # Examples
+//! ```no_run
+//! use bytes::BytesMut;
+//! use reqse::{Method, ResponseBuilder};
+//! use tokio::net::TcpListener;
+//!
+//! # async fn run() -> std::io::Result<()> {
+//! let listener = TcpListener::bind("localhost:3000").await?;
+//! let (mut connection, _) = listener.accept().await?;
+//! let mut buf = BytesMut::new();
+//!
+//! while let Some(request) = reqse::tokio::read_request(&mut connection, &mut buf).await? {
+//!     let response = match (request.method(), request.uri()) {
+//!         (Method::Get, "/") => ResponseBuilder::ok(),
+//!         _ => ResponseBuilder::not_found(),
+//!     };
+//!     let keep_alive = reqse::should_keep_alive(&request.as_request(), &response);
+//!     reqse::tokio::write_response(&mut connection, response).await?;
+//!
+//!     if !keep_alive {
+//!         break;
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::io;
+
+use ::tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use bytes::{Buf, BytesMut};
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::{
+    Error, OwnedRequest, Request, ResponseBuilder, chunked,
+    framing::{self, BodyLength},
+};
+
+/// default maximum number of bytes a single request (header and body) may occupy
+pub const MAX_REQUEST_LEN: usize = 64 * 1024;
+
+/// reads the next request from `reader`
+///
+/// bytes are read into `buf` and the ones after the request, eg: of a pipelined request, stay
+/// there for the next call, so the same `buf` has to be passed for every request read from
+/// `reader`. `tokio_util::codec::FramedRead` with an `HttpCodec` does the same for a stream of
+/// requests.
+///
+/// # Error
+/// - returns `Ok(None)` if the reader reached EOF before any byte of the request
+/// - returns `io::ErrorKind::UnexpectedEof` if the reader reached EOF in the middle of a request
+/// - returns `io::ErrorKind::InvalidData` if the request is malformed (see
+///   `Request::from_bytes_strict`) or longer than `MAX_REQUEST_LEN`
+pub async fn read_request<R>(reader: &mut R, buf: &mut BytesMut) -> io::Result<Option<OwnedRequest>>
+where
+    R: AsyncRead + Unpin,
+{
+    let mut codec = HttpCodec::new();
+
+    loop {
+        if let Some(request) = codec.decode(buf)? {
+            return Ok(Some(request));
+        }
+
+        if reader.read_buf(buf).await? == 0 {
+            if buf.is_empty() {
+                return Ok(None);
+            }
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+    }
+}
+
+/// serializes `response` and writes it to `writer`
+///
+/// a file range is read into memory first, see `ResponseBuilder::write_to`
+///
+/// # Error
+/// - `io::ErrorKind::InvalidData` if the response does not pass `ResponseBuilder::validate`
+/// - the error of reading the file range
+pub async fn write_response<W>(writer: &mut W, response: ResponseBuilder) -> io::Result<()>
+where
+    W: AsyncWrite + Unpin,
+{
+    let mut buf = Vec::new();
+    response.write_to(&mut buf)?;
+    writer.write_all(&buf).await?;
+    writer.flush().await
+}
+
+/// a `tokio_util::codec` decoding requests and encoding responses
+///
+/// wrapping a stream in `tokio_util::codec::Framed` with this codec gives a stream of
+/// `OwnedRequest`s and a sink of `ResponseBuilder`s.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HttpCodec {
+    max_request_len: usize,
+    progress: Progress,
+}
+
+impl HttpCodec {
+    pub fn new() -> Self {
+        Self {
+            max_request_len: MAX_REQUEST_LEN,
+            progress: Progress::default(),
+        }
+    }
+
+    /// maximum number of bytes a single request (header and body) may occupy, larger requests
+    /// result in a decoding error
+    pub fn max_request_len(&self) -> usize {
+        self.max_request_len
+    }
+
+    pub fn max_request_len_mut(&mut self) -> &mut usize {
+        &mut self.max_request_len
+    }
+}
+
+impl Default for HttpCodec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// how much of a partially received request was checked, so every byte is looked at once
+/// instead of parsing the whole buffer again whenever more bytes arrive
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct Progress {
+    /// number of bytes searched for the end of the head
+    searched: usize,
+    /// length of the head and framing of the body, once the head is complete
+    head: Option<(usize, BodyLength)>,
+    /// start of the first chunk of a chunked body which was not received completely
+    chunk: usize,
+}
+
+impl HttpCodec {
+    /// whether `src` starts with a complete request, advances `progress` as far as possible
+    fn is_complete(&mut self, src: &[u8]) -> Result<bool, Error> {
+        let (head_len, body_len) = match self.progress.head {
+            Some(head) => head,
+            None => {
+                // the end of the head may span the bytes searched before and the new ones
+                let start = self.progress.searched.saturating_sub(3);
+                let Some(end) = src[start..].windows(4).position(|w| w == b"\r\n\r\n") else {
+                    self.progress.searched = src.len();
+                    return Ok(false);
+                };
+                let head_len = start + end + 4;
+
+                let (request, _) = Request::parse_head_strict(&src[..head_len])?;
+                let body_len = match framing::request_body_length_strict(request.header())? {
+                    BodyLength::UntilEof => return Err(Error::InvalidHeader),
+                    body_len => body_len,
+                };
+
+                self.progress.head = Some((head_len, body_len));
+                self.progress.chunk = head_len;
+                (head_len, body_len)
+            }
+        };
+
+        match body_len {
+            BodyLength::Fixed(len) => Ok(src.len() - head_len >= len),
+            BodyLength::Chunked => chunked::skip_chunks(src, &mut self.progress.chunk),
+            BodyLength::UntilEof => Err(Error::InvalidHeader),
+        }
+    }
+}
+
+impl Decoder for HttpCodec {
+    type Item = OwnedRequest;
+    type Error = io::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        if !self.is_complete(src)? {
+            if src.len() > self.max_request_len {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "request exceeds maximum length",
+                ));
+            }
+            return Ok(None);
+        }
+
+        self.progress = Progress::default();
+
+        let (request, len) = Request::parse_strict(src)?;
+        let request = OwnedRequest::from(request);
+        src.advance(len);
+        Ok(Some(request))
+    }
+}
+
+impl Encoder<ResponseBuilder> for HttpCodec {
+    type Error = io::Error;
+
+    fn encode(&mut self, item: ResponseBuilder, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        let mut buf = Vec::new();
+        item.write_to(&mut buf)?;
+        dst.extend_from_slice(&buf);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Method;
+
+    #[::tokio::test]
+    async fn test_read_request_pipelined() {
+        let mut reader: &[u8] =
+            b"POST /a HTTP/1.1\r\nContent-Length: 2\r\n\r\nhiGET /b HTTP/1.1\r\n\r\n";
+        let mut buf = BytesMut::new();
+
+        let request = read_request(&mut reader, &mut buf).await.unwrap().unwrap();
+        assert_eq!(request.method(), Method::Post);
+        assert_eq!(request.uri(), "/a");
+        assert_eq!(request.body(), b"hi");
+
+        let request = read_request(&mut reader, &mut buf).await.unwrap().unwrap();
+        assert_eq!(request.method(), Method::Get);
+        assert_eq!(request.uri(), "/b");
+
+        assert!(read_request(&mut reader, &mut buf).await.unwrap().is_none());
+    }
+
+    #[::tokio::test]
+    async fn test_read_request_unexpected_eof() {
+        let mut reader: &[u8] = b"GET / HTTP/1.1\r\n";
+        let err = read_request(&mut reader, &mut BytesMut::new())
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[::tokio::test]
+    async fn test_read_request_trickle() {
+        let raw = b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n2\r\nhi\r\n0\r\n\r\nGET";
+        // the pipe passes one byte per read
+        let (mut reader, mut writer) = ::tokio::io::duplex(1);
+        ::tokio::spawn(async move { writer.write_all(raw).await });
+        let mut buf = BytesMut::new();
+
+        let request = read_request(&mut reader, &mut buf).await.unwrap().unwrap();
+        assert_eq!(request.body(), b"2\r\nhi\r\n0\r\n\r\n");
+        assert_eq!(&buf[..], b"");
+    }
+
+    #[test]
+    fn test_codec() {
+        let mut codec = HttpCodec::new();
+        let mut buf = BytesMut::from(&b"GET / HTTP/1.1\r\n"[..]);
+
+        assert!(codec.decode(&mut buf).unwrap().is_none());
+
+        buf.extend_from_slice(b"\r\nGET");
+        let request = codec.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(request.uri(), "/");
+        assert_eq!(&buf[..], b"GET");
+
+        // the head and the chunks are checked as they arrive
+        buf.clear();
+        for byte in b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n2\r\nhi\r\n0\r\n\r" {
+            buf.extend_from_slice(&[*byte]);
+            assert!(codec.decode(&mut buf).unwrap().is_none());
+        }
+        buf.extend_from_slice(b"\n");
+        let request = codec.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(request.method(), Method::Post);
+        assert!(buf.is_empty());
+
+        buf.extend_from_slice(b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\nx\r\n");
+        assert_eq!(
+            codec.decode(&mut buf).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+
+        let mut dst = BytesMut::new();
+        codec
+            .encode(ResponseBuilder::not_found(), &mut dst)
+            .unwrap();
+        assert_eq!(
+            &dst[..],
+            b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n"
+        );
+    }
+}
@@ -0,0 +1,143 @@
+use std::{
+    fmt,
+    io::{self, Read, Write},
+    net::TcpStream,
+    sync::{Arc, Mutex},
+};
+
+pub(crate) type OnUpgrade = Box<dyn FnOnce(Upgraded) + Send>;
+
+/// takes over the connection once a `101 Switching Protocols` response was sent
+///
+/// `Server` puts an `Upgrade` into the extensions of every request. A handler accepting one of
+/// the protocols offered in `Request::upgrade` registers a function with `on_upgrade` and
+/// answers with `ResponseBuilder::switching_protocols`. After the response was sent the server
+/// stops speaking HTTP on the connection and calls the function with the raw stream.
+///
+/// A handler answering with `ResponseBuilder::event_stream` uses the same mechanism to write
+/// the body of the event stream, see `sse::EventStream`. The connection is closed once the
+/// function returns.
+///
+/// A `101` response without a registered function is a bug in the handler and is replaced by
+/// `500 Internal Server Error`, as is a `101` response to a request which did not ask for an
+/// upgrade.
+///
+/// # Examples
+/// ```
+/// use reqse::{Request, ResponseBuilder, Upgrade};
+/// use std::io::{self, Read, Write};
+///
+/// fn echo(request: Request) -> io::Result<ResponseBuilder> {
+///     let upgrade = request.extensions().get::<Upgrade>();
+///     let (Some("echo"), Some(upgrade)) = (request.upgrade(), upgrade) else {
+///         return Ok(ResponseBuilder::bad_request());
+///     };
+///
+///     upgrade.on_upgrade(|mut upgraded| {
+///         let mut buf = [0; 1024];
+///         while let Ok(n @ 1..) = upgraded.read(&mut buf) {
+///             if upgraded.write_all(&buf[..n]).is_err() {
+///                 break;
+///             }
+///         }
+///     });
+///
+///     Ok(ResponseBuilder::switching_protocols("echo"))
+/// }
+/// ```
+#[derive(Clone, Default)]
+pub struct Upgrade {
+    on_upgrade: Arc<Mutex<Option<OnUpgrade>>>,
+}
+
+impl Upgrade {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// registers `f` to be called with the connection after the `101` response was sent,
+    /// replacing a function registered before
+    ///
+    /// `f` runs on a thread of its own, so a long lived connection does not occupy a worker of
+    /// the server. Upgraded connections are not closed when the server shuts down.
+    pub fn on_upgrade<F>(&self, f: F)
+    where
+        F: FnOnce(Upgraded) + Send + 'static,
+    {
+        *self.lock() = Some(Box::new(f));
+    }
+
+    /// removes the registered function
+    pub(crate) fn take(&self) -> Option<OnUpgrade> {
+        self.lock().take()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<OnUpgrade>> {
+        self.on_upgrade
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+impl fmt::Debug for Upgrade {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Upgrade")
+            .field("registered", &self.lock().is_some())
+            .finish()
+    }
+}
+
+/// a connection handed over after `101 Switching Protocols`, see `Upgrade` and
+/// `websocket::connect`
+///
+/// the peer may have sent data of the new protocol right behind its request or response, those
+/// bytes were already read from the stream and are returned first by `read`.
+#[derive(Debug)]
+pub struct Upgraded {
+    stream: TcpStream,
+    read_buf: Vec<u8>,
+}
+
+impl Upgraded {
+    pub(crate) fn new(stream: TcpStream, read_buf: Vec<u8>) -> Self {
+        Self { stream, read_buf }
+    }
+
+    pub fn stream(&self) -> &TcpStream {
+        &self.stream
+    }
+
+    /// bytes received after the request or response which have not been read yet
+    pub fn read_buf(&self) -> &[u8] {
+        &self.read_buf
+    }
+
+    /// returns the stream and the bytes received after the request or response
+    pub fn into_parts(self) -> (TcpStream, Vec<u8>) {
+        (self.stream, self.read_buf)
+    }
+}
+
+impl Read for Upgraded {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.read_buf.is_empty() {
+            return self.stream.read(buf);
+        }
+
+        let n = buf.len().min(self.read_buf.len());
+        buf[..n].copy_from_slice(&self.read_buf[..n]);
+        self.read_buf.drain(..n);
+
+        Ok(n)
+    }
+}
+
+impl Write for Upgraded {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.stream.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush()
+    }
+}
@@ -0,0 +1,68 @@
+use std::io::{self, IoSlice, Write};
+
+/// writes all of `bufs` with `write_vectored`, the stable counterpart of
+/// `Write::write_all_vectored`
+pub(crate) fn write_all_vectored<W: Write + ?Sized>(
+    writer: &mut W,
+    mut bufs: &mut [IoSlice<'_>],
+) -> io::Result<()> {
+    IoSlice::advance_slices(&mut bufs, 0);
+
+    while !bufs.is_empty() {
+        match writer.write_vectored(bufs) {
+            Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+            Ok(n) => IoSlice::advance_slices(&mut bufs, n),
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => (),
+            Err(err) => return Err(err),
+        }
+    }
+
+    Ok(())
+}
+
+/// length of a header line `\r\n<key>: <value>`
+pub(crate) fn header_line_len(key: &str, value: &str) -> usize {
+    2 + key.len() + 2 + value.len()
+}
+
+/// appends a header line `\r\n<key>: <value>` to `buf`
+pub(crate) fn push_header_line(buf: &mut Vec<u8>, key: &str, value: &str) {
+    buf.extend_from_slice(b"\r\n");
+    buf.extend_from_slice(key.as_bytes());
+    buf.extend_from_slice(b": ");
+    buf.extend_from_slice(value.as_bytes());
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// accepts at most three bytes per call
+    struct Trickle(Vec<u8>);
+
+    impl Write for Trickle {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            let n = buf.len().min(3);
+            self.0.extend_from_slice(&buf[..n]);
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_write_all_vectored() {
+        let mut writer = Trickle(Vec::new());
+        let mut bufs = [
+            IoSlice::new(b"Hello"),
+            IoSlice::new(b""),
+            IoSlice::new(b" World"),
+        ];
+
+        write_all_vectored(&mut writer, &mut bufs).unwrap();
+
+        assert_eq!(writer.0, b"Hello World");
+    }
+}
@@ -0,0 +1,1045 @@
+//! WebSocket connections, see RFC 6455
+//!
+//! a handler accepts the handshake with `handshake`, takes over the connection with `Upgrade`
+//! and speaks the WebSocket protocol over it with `WebSocket`. Clients open a connection with
+//! `connect`.
+//!
+//! # Examples
+//! ```no_run
+//! use reqse::{Request, ResponseBuilder, Upgrade, websocket::{self, Message, WebSocket}};
+//! use std::io;
+//!
+//! fn echo(request: Request) -> io::Result<ResponseBuilder> {
+//!     let Ok(response) = websocket::handshake(&request) else {
+//!         return Ok(ResponseBuilder::bad_request());
+//!     };
+//!
+//!     let upgrade = request.extensions().get::<Upgrade>().unwrap();
+//!     upgrade.on_upgrade(|upgraded| {
+//!         let mut socket = WebSocket::server(upgraded);
+//!         while let Ok(message) = socket.read() {
+//!             match message {
+//!                 Message::Text(_) | Message::Binary(_) => {
+//!                     if socket.send(message).is_err() {
+//!                         break;
+//!                     }
+//!                 }
+//!                 Message::Close(_) => break,
+//!                 Message::Ping(_) | Message::Pong(_) => (),
+//!             }
+//!         }
+//!     });
+//!
+//!     Ok(response)
+//! }
+//! ```
+
+mod base64;
+#[cfg(feature = "deflate")]
+mod deflate;
+mod frame;
+mod random;
+mod sha1;
+
+#[cfg(feature = "deflate")]
+pub use deflate::Deflate;
+pub use frame::{Frame, OpCode};
+
+use std::io::{self, Read, Write};
+
+use crate::{
+    Error, Method, OwnedResponse, Request, RequestBuilder, Response, ResponseBuilder, Status,
+    Upgraded, Version, client::Client, connection,
+};
+
+/// appended to `Sec-WebSocket-Key` before hashing it, see RFC 6455 section 1.3
+const GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// default maximum number of bytes a single message may occupy
+pub const MAX_MESSAGE_LEN: usize = 16 * 1024 * 1024;
+
+/// computes the `Sec-WebSocket-Accept` value answering the `Sec-WebSocket-Key` `key`
+///
+/// # Examples
+/// ```
+/// use reqse::websocket;
+///
+/// assert_eq!(
+///     websocket::accept_key("dGhlIHNhbXBsZSBub25jZQ=="),
+///     "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+/// );
+/// ```
+pub fn accept_key(key: &str) -> String {
+    let mut data = key.as_bytes().to_vec();
+    data.extend_from_slice(GUID.as_bytes());
+    base64::encode(&sha1::digest(&data))
+}
+
+/// whether `key` is a valid `Sec-WebSocket-Key`, that is 16 bytes encoded with base64
+pub fn is_valid_key(key: &str) -> bool {
+    base64::decode(key).is_some_and(|key| key.len() == 16)
+}
+
+/// checks the opening handshake of a client and returns the response accepting it, see
+/// `ResponseBuilder::websocket`
+///
+/// the request has to be a HTTP/1.1 `GET` asking to upgrade to `websocket` with version 13
+/// and a valid `Sec-WebSocket-Key`
+///
+/// # Error
+/// - `Error::InvalidHeader` if the request is not a valid opening handshake
+///
+/// # Examples
+/// ```
+/// use reqse::{Request, Status, websocket};
+///
+/// let raw_request = b"GET /chat HTTP/1.1\r\nHost: example.com\r\nConnection: Upgrade\r\n\
+///     Upgrade: websocket\r\nSec-WebSocket-Version: 13\r\n\
+///     Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\r\n";
+/// let request = Request::from_bytes(raw_request).unwrap();
+///
+/// let response = websocket::handshake(&request).unwrap();
+/// assert_eq!(response.status(), Status::SwitchingProtocols);
+/// assert_eq!(
+///     response.header().get("Sec-WebSocket-Accept").unwrap(),
+///     "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+/// );
+/// ```
+pub fn handshake(request: &Request) -> Result<ResponseBuilder, Error> {
+    let upgrade = request
+        .upgrade()
+        .is_some_and(|upgrade| connection::has_token(upgrade, "websocket"));
+    let version = request.header().get("Sec-WebSocket-Version") == Some("13");
+    let key = request
+        .header()
+        .get("Sec-WebSocket-Key")
+        .filter(|key| is_valid_key(key));
+
+    match key {
+        Some(key)
+            if upgrade
+                && version
+                && request.method() == Method::Get
+                && request.version() == Version::Http1 =>
+        {
+            Ok(ResponseBuilder::websocket(key))
+        }
+        _ => Err(Error::InvalidHeader),
+    }
+}
+
+/// like `handshake`, but also accepts permessage-deflate if the client offers it
+///
+/// `config` holds the parameters the server demands, the negotiated ones are returned together
+/// with the response and have to be passed to `WebSocket::set_deflate` after the upgrade. Only
+/// available with the `deflate` feature enabled.
+///
+/// # Error
+/// - `Error::InvalidHeader` if the request is not a valid opening handshake
+#[cfg(feature = "deflate")]
+pub fn handshake_deflate(
+    request: &Request,
+    config: Deflate,
+) -> Result<(ResponseBuilder, Option<Deflate>), Error> {
+    let mut response = handshake(request)?;
+
+    let offers = request.header().get("Sec-WebSocket-Extensions");
+    let Some((deflate, value)) = offers.and_then(|offers| config.accept(offers)) else {
+        return Ok((response, None));
+    };
+
+    response
+        .header_mut()
+        .insert("Sec-WebSocket-Extensions".into(), value);
+
+    Ok((response, Some(deflate)))
+}
+
+/// a random `Sec-WebSocket-Key` for the opening handshake of a client
+pub(crate) fn generate_key() -> String {
+    base64::encode(&random::random_bytes::<16>())
+}
+
+/// checks the response of a server to the opening handshake `request` of a client, see
+/// `RequestBuilder::websocket`
+///
+/// the response has to switch to `websocket` and answer the `Sec-WebSocket-Key` of the request.
+/// A subprotocol in `Sec-WebSocket-Protocol` has to be one the request offered. The only
+/// supported extension is permessage-deflate with the `deflate` feature enabled, and only if the
+/// request offered it, a response with any other `Sec-WebSocket-Extensions` is rejected.
+///
+/// # Error
+/// - `Error::InvalidHeader` if the response does not accept the handshake
+///
+/// # Examples
+/// ```
+/// use reqse::{RequestBuilder, Response, websocket};
+///
+/// let request = RequestBuilder::websocket("/chat".into());
+/// let key = request.header().get("Sec-WebSocket-Key").unwrap();
+///
+/// let raw_response = format!(
+///     "HTTP/1.1 101 Switching Protocols\r\nConnection: Upgrade\r\nUpgrade: websocket\r\n\
+///     Sec-WebSocket-Accept: {}\r\n\r\n",
+///     websocket::accept_key(key)
+/// );
+/// let response = Response::from_bytes(raw_response.as_bytes()).unwrap();
+///
+/// assert!(websocket::validate_response(&request, &response).is_ok());
+/// ```
+pub fn validate_response(request: &RequestBuilder, response: &Response) -> Result<(), Error> {
+    let header = response.header();
+
+    let upgrade = header
+        .get("Upgrade")
+        .is_some_and(|upgrade| connection::has_token(upgrade, "websocket"));
+    let accept = request_field(request, "Sec-WebSocket-Key")
+        .is_some_and(|key| header.get("Sec-WebSocket-Accept") == Some(&accept_key(key)));
+    let protocol = header.get("Sec-WebSocket-Protocol").is_none_or(|protocol| {
+        request_field(request, "Sec-WebSocket-Protocol")
+            .is_some_and(|offered| offered.split(',').any(|p| p.trim() == protocol.trim()))
+    });
+
+    let valid = response.status() == Status::SwitchingProtocols
+        && upgrade
+        && connection::has_option(header.iter(), "upgrade")
+        && accept
+        && protocol;
+
+    if !valid {
+        return Err(Error::InvalidHeader);
+    }
+
+    #[cfg(feature = "deflate")]
+    negotiate_deflate(request, response)?;
+
+    #[cfg(not(feature = "deflate"))]
+    if header.contains("Sec-WebSocket-Extensions") {
+        return Err(Error::InvalidHeader);
+    }
+
+    Ok(())
+}
+
+/// the permessage-deflate parameters accepted by `response`, `None` if it accepted no extension
+///
+/// # Error
+/// - `Error::InvalidHeader` if the response accepted an extension the request did not offer
+#[cfg(feature = "deflate")]
+pub(crate) fn negotiate_deflate(
+    request: &RequestBuilder,
+    response: &Response,
+) -> Result<Option<Deflate>, Error> {
+    let offer = request_field(request, "Sec-WebSocket-Extensions");
+    response
+        .header()
+        .get("Sec-WebSocket-Extensions")
+        .map(|accepted| Deflate::from_response(offer, accepted))
+        .transpose()
+}
+
+fn request_field<'a>(request: &'a RequestBuilder, name: &str) -> Option<&'a str> {
+    request
+        .header()
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.as_str())
+}
+
+/// opens a WebSocket connection to `addr` (eg: `localhost:3000`) with the default `Client`
+/// settings, see `Client::websocket`
+///
+/// # Examples
+/// ```no_run
+/// use reqse::{RequestBuilder, websocket::{self, Message}};
+///
+/// let request = RequestBuilder::websocket("/echo".into());
+/// let (mut socket, _) = websocket::connect("localhost:3000", request).unwrap();
+///
+/// socket.send(Message::Text("Hello".into())).unwrap();
+/// assert_eq!(socket.read().unwrap(), Message::Text("Hello".into()));
+/// socket.close(None).unwrap();
+/// ```
+pub fn connect(
+    addr: &str,
+    request: RequestBuilder,
+) -> io::Result<(WebSocket<Upgraded>, OwnedResponse)> {
+    Client::new().websocket(addr, request)
+}
+
+/// a complete message, fragmented messages are reassembled by `WebSocket::read`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Message {
+    Text(String),
+    Binary(Vec<u8>),
+    Ping(Vec<u8>),
+    Pong(Vec<u8>),
+    Close(Option<CloseFrame>),
+}
+
+/// the status code and reason of a close frame, see RFC 6455 section 7.4
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CloseFrame {
+    code: u16,
+    reason: String,
+}
+
+impl CloseFrame {
+    /// normal closure
+    pub const NORMAL: u16 = 1000;
+    pub const GOING_AWAY: u16 = 1001;
+    pub const PROTOCOL_ERROR: u16 = 1002;
+    pub const UNSUPPORTED_DATA: u16 = 1003;
+    /// a text message was not valid UTF-8
+    pub const INVALID_PAYLOAD: u16 = 1007;
+    pub const POLICY_VIOLATION: u16 = 1008;
+    pub const MESSAGE_TOO_BIG: u16 = 1009;
+    pub const INTERNAL_ERROR: u16 = 1011;
+
+    pub fn new(code: u16, reason: String) -> Self {
+        Self { code, reason }
+    }
+
+    pub fn code(&self) -> u16 {
+        self.code
+    }
+
+    pub fn reason(&self) -> &str {
+        &self.reason
+    }
+
+    /// whether `code` may be sent in a close frame, the codes 1004 to 1006 and 1015 are only
+    /// used internally by implementations and 1016 to 2999 are not assigned yet
+    pub fn is_valid_code(code: u16) -> bool {
+        matches!(code, 1000..=1003 | 1007..=1014 | 3000..=4999)
+    }
+
+    fn decode(payload: &[u8]) -> Result<Option<Self>, Error> {
+        match payload {
+            [] => Ok(None),
+            [high, low, reason @ ..] => {
+                let code = u16::from_be_bytes([*high, *low]);
+                if !Self::is_valid_code(code) {
+                    return Err(Error::InvalidFrame);
+                }
+                let reason = std::str::from_utf8(reason).map_err(|_| Error::InvalidUtf8)?;
+                Ok(Some(Self::new(code, reason.to_owned())))
+            }
+            [_] => Err(Error::InvalidFrame),
+        }
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut payload = self.code.to_be_bytes().to_vec();
+        payload.extend_from_slice(self.reason.as_bytes());
+        payload
+    }
+}
+
+/// which end of the connection a `WebSocket` is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Role {
+    Server,
+    Client,
+}
+
+/// a WebSocket connection over `stream`
+///
+/// `read` reassembles fragmented messages, answers pings and close frames and closes the
+/// connection with the matching status code when the peer violates the protocol, eg: sends a
+/// text message which is not valid UTF-8.
+#[derive(Debug)]
+pub struct WebSocket<S> {
+    stream: S,
+    role: Role,
+    read_buf: Vec<u8>,
+    /// the fragmented message being received
+    fragments: Option<Fragments>,
+    /// length of the prefix of a fragmented text message which is known to be valid UTF-8
+    utf8_checked: usize,
+    max_message_len: usize,
+    close_sent: bool,
+    close_received: bool,
+    #[cfg(feature = "deflate")]
+    deflate: Option<(Deflate, deflate::Context)>,
+}
+
+/// the fragments of a message received so far
+#[derive(Debug)]
+struct Fragments {
+    opcode: OpCode,
+    /// whether the message is compressed with permessage-deflate
+    compressed: bool,
+    payload: Vec<u8>,
+}
+
+impl<S: Read + Write> WebSocket<S> {
+    /// the server end of a connection whose opening handshake is done, see `handshake`
+    pub fn server(stream: S) -> Self {
+        Self::new(stream, Role::Server)
+    }
+
+    /// the client end of a connection whose opening handshake is done, see `connect`
+    ///
+    /// every frame sent by a client is masked with a random key
+    pub fn client(stream: S) -> Self {
+        Self::new(stream, Role::Client)
+    }
+
+    fn new(stream: S, role: Role) -> Self {
+        Self {
+            stream,
+            role,
+            read_buf: Vec::new(),
+            fragments: None,
+            utf8_checked: 0,
+            max_message_len: MAX_MESSAGE_LEN,
+            close_sent: false,
+            close_received: false,
+            #[cfg(feature = "deflate")]
+            deflate: None,
+        }
+    }
+
+    pub fn stream(&self) -> &S {
+        &self.stream
+    }
+
+    pub fn stream_mut(&mut self) -> &mut S {
+        &mut self.stream
+    }
+
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+
+    /// maximum number of bytes a single message may occupy, larger messages close the
+    /// connection with `CloseFrame::MESSAGE_TOO_BIG`
+    pub fn max_message_len(&self) -> usize {
+        self.max_message_len
+    }
+
+    pub fn max_message_len_mut(&mut self) -> &mut usize {
+        &mut self.max_message_len
+    }
+
+    /// the negotiated permessage-deflate parameters, `None` if messages are not compressed
+    #[cfg(feature = "deflate")]
+    pub fn deflate(&self) -> Option<Deflate> {
+        self.deflate.as_ref().map(|(deflate, _)| *deflate)
+    }
+
+    /// compresses messages with permessage-deflate, see `handshake_deflate`
+    ///
+    /// the compression state is reset, so this should only be called right after the handshake
+    #[cfg(feature = "deflate")]
+    pub fn set_deflate(&mut self, deflate: Option<Deflate>) {
+        let server = self.role == Role::Server;
+        self.deflate = deflate.map(|deflate| (deflate, deflate::Context::new(deflate, server)));
+    }
+
+    fn compressed(&self) -> bool {
+        #[cfg(feature = "deflate")]
+        return self.deflate.is_some();
+        #[cfg(not(feature = "deflate"))]
+        return false;
+    }
+
+    /// decompresses the payload of a message if permessage-deflate is negotiated
+    fn decompress(&mut self, payload: Vec<u8>) -> Result<Vec<u8>, Error> {
+        #[cfg(feature = "deflate")]
+        if let Some((_, context)) = &mut self.deflate {
+            return context.decompress(&payload, self.max_message_len);
+        }
+
+        Ok(payload)
+    }
+
+    /// compresses the payload of a message if permessage-deflate is negotiated
+    #[cfg_attr(not(feature = "deflate"), allow(unused_variables))]
+    fn compress(&mut self, frame: &mut Frame) -> io::Result<()> {
+        #[cfg(feature = "deflate")]
+        if let Some((_, context)) = &mut self.deflate {
+            *frame.payload_mut() = context.compress(frame.payload())?;
+            *frame.rsv1_mut() = true;
+        }
+
+        Ok(())
+    }
+
+    /// reads the next message
+    ///
+    /// pings are answered with a pong before they are returned. A close frame is answered with
+    /// a close frame unless one was sent already, afterwards the connection should be dropped.
+    ///
+    /// # Error
+    /// - `io::ErrorKind::InvalidData` if the peer violated the protocol, the connection was
+    ///   closed with the matching status code
+    /// - `io::ErrorKind::NotConnected` if a close frame was received already
+    /// - `io::ErrorKind::UnexpectedEof` if the stream ended without close frame
+    pub fn read(&mut self) -> io::Result<Message> {
+        if self.close_received {
+            return Err(io::Error::new(
+                io::ErrorKind::NotConnected,
+                "websocket is closed",
+            ));
+        }
+
+        loop {
+            let frame = self.read_frame()?;
+
+            match self.handle_frame(frame) {
+                Ok(Some(message)) => return Ok(message),
+                Ok(None) => (),
+                Err(err) => return Err(self.fail(err)),
+            }
+        }
+    }
+
+    /// returns the message completed by `frame`, if any
+    fn handle_frame(&mut self, frame: Frame) -> Result<Option<Message>, Error> {
+        let fin = frame.fin();
+        let opcode = frame.opcode();
+        let compressed = frame.rsv1();
+
+        // only the first frame of a data message may be marked as compressed
+        let data = matches!(opcode, OpCode::Text | OpCode::Binary);
+        if compressed && !(data && self.compressed()) {
+            return Err(Error::InvalidFrame);
+        }
+
+        let payload = frame.into_payload();
+
+        match opcode {
+            OpCode::Ping => {
+                let pong = Frame::new(true, OpCode::Pong, payload.clone());
+                // a failed pong surfaces on the next write
+                let _ = self.send_frame(pong);
+                Ok(Some(Message::Ping(payload)))
+            }
+            OpCode::Pong => Ok(Some(Message::Pong(payload))),
+            OpCode::Close => {
+                let close = CloseFrame::decode(&payload)?;
+                self.close_received = true;
+                // the reply echoes the status code, see RFC 6455 section 5.5.1
+                if !self.close_sent {
+                    let reply = close
+                        .as_ref()
+                        .map(|close| CloseFrame::new(close.code(), String::new()));
+                    let _ = self.send(Message::Close(reply));
+                }
+                Ok(Some(Message::Close(close)))
+            }
+            OpCode::Text | OpCode::Binary if self.fragments.is_some() => Err(Error::InvalidFrame),
+            OpCode::Text | OpCode::Binary => {
+                self.utf8_checked = 0;
+                self.fragments = Some(Fragments {
+                    opcode,
+                    compressed,
+                    payload: Vec::new(),
+                });
+                self.push_fragment(payload, fin)
+            }
+            OpCode::Continuation if self.fragments.is_none() => Err(Error::InvalidFrame),
+            OpCode::Continuation => self.push_fragment(payload, fin),
+        }
+    }
+
+    /// appends a fragment to the message being received and returns it once `fin` is set
+    fn push_fragment(&mut self, payload: Vec<u8>, fin: bool) -> Result<Option<Message>, Error> {
+        let Some(fragments) = &mut self.fragments else {
+            return Err(Error::InvalidFrame);
+        };
+
+        if fragments.payload.len() + payload.len() > self.max_message_len {
+            return Err(Error::TooLarge);
+        }
+
+        fragments.payload.extend_from_slice(&payload);
+
+        // invalid UTF-8 fails the connection right away instead of after the last fragment,
+        // compressed messages can only be checked once they are complete
+        if fragments.opcode == OpCode::Text && !fragments.compressed {
+            match std::str::from_utf8(&fragments.payload[self.utf8_checked..]) {
+                Ok(_) => self.utf8_checked = fragments.payload.len(),
+                Err(err) if err.error_len().is_none() && !fin => {
+                    self.utf8_checked += err.valid_up_to();
+                }
+                Err(_) => return Err(Error::InvalidUtf8),
+            }
+        }
+
+        if !fin {
+            return Ok(None);
+        }
+
+        let Some(fragments) = self.fragments.take() else {
+            return Err(Error::InvalidFrame);
+        };
+
+        let message = if fragments.compressed {
+            self.decompress(fragments.payload)?
+        } else {
+            fragments.payload
+        };
+
+        Ok(Some(match fragments.opcode {
+            OpCode::Text => {
+                Message::Text(String::from_utf8(message).map_err(|_| Error::InvalidUtf8)?)
+            }
+            _ => Message::Binary(message),
+        }))
+    }
+
+    fn read_frame(&mut self) -> io::Result<Frame> {
+        let mut chunk = [0_u8; 4096];
+
+        loop {
+            match Frame::decode_limited(&self.read_buf, self.max_message_len) {
+                Ok((frame, len)) => {
+                    self.read_buf.drain(..len);
+
+                    // clients mask every frame, servers none, see RFC 6455 section 5.1
+                    let masked = frame.mask().is_some();
+                    if masked != (self.role == Role::Server) {
+                        return Err(self.fail(Error::InvalidFrame));
+                    }
+
+                    return Ok(frame);
+                }
+                Err(Error::NotEnoughData) => (),
+                Err(err) => return Err(self.fail(err)),
+            }
+
+            match self.stream.read(&mut chunk) {
+                Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+                Ok(n) => self.read_buf.extend_from_slice(&chunk[..n]),
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => (),
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// closes the connection because of `err` and returns the error for the caller
+    fn fail(&mut self, err: Error) -> io::Error {
+        let code = match err {
+            Error::InvalidUtf8 => CloseFrame::INVALID_PAYLOAD,
+            Error::TooLarge => CloseFrame::MESSAGE_TOO_BIG,
+            _ => CloseFrame::PROTOCOL_ERROR,
+        };
+
+        if !self.close_sent {
+            let _ = self.send(Message::Close(Some(CloseFrame::new(code, String::new()))));
+        }
+        self.close_received = true;
+
+        err.into()
+    }
+
+    /// sends `message` as a single frame
+    ///
+    /// text and binary messages are compressed if permessage-deflate is negotiated, see
+    /// `set_deflate`.
+    ///
+    /// # Error
+    /// - `io::ErrorKind::NotConnected` if a close frame was sent already
+    pub fn send(&mut self, message: Message) -> io::Result<()> {
+        let mut frame = match message {
+            Message::Text(text) => Frame::new(true, OpCode::Text, text.into_bytes()),
+            Message::Binary(data) => Frame::new(true, OpCode::Binary, data),
+            Message::Ping(data) => Frame::new(true, OpCode::Ping, data),
+            Message::Pong(data) => Frame::new(true, OpCode::Pong, data),
+            Message::Close(close) => {
+                let payload = close.map(|close| close.encode()).unwrap_or_default();
+                Frame::new(true, OpCode::Close, payload)
+            }
+        };
+
+        if matches!(frame.opcode(), OpCode::Text | OpCode::Binary) {
+            self.compress(&mut frame)?;
+        }
+
+        self.send_frame(frame)
+    }
+
+    /// sends a close frame, see `send`
+    pub fn close(&mut self, close: Option<CloseFrame>) -> io::Result<()> {
+        self.send(Message::Close(close))
+    }
+
+    /// sends a single frame, which allows to send a message in fragments
+    ///
+    /// # Error
+    /// - `io::ErrorKind::NotConnected` if a close frame was sent already
+    pub fn send_frame(&mut self, mut frame: Frame) -> io::Result<()> {
+        if self.close_sent {
+            return Err(io::Error::new(
+                io::ErrorKind::NotConnected,
+                "websocket is closed",
+            ));
+        }
+
+        self.close_sent = frame.opcode() == OpCode::Close;
+
+        if self.role == Role::Client {
+            *frame.mask_mut() = Some(random::random_bytes());
+        }
+
+        self.stream.write_all(&frame.encode())?;
+        self.stream.flush()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Cursor;
+
+    /// a stream which reads `input` and collects everything written
+    struct Mock {
+        input: Cursor<Vec<u8>>,
+        output: Vec<u8>,
+    }
+
+    impl Read for Mock {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.input.read(buf)
+        }
+    }
+
+    impl Write for Mock {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.output.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// a masked frame as sent by a client
+    fn client_frame(fin: bool, opcode: OpCode, payload: &[u8]) -> Vec<u8> {
+        let mut frame = Frame::new(fin, opcode, payload.to_vec());
+        *frame.mask_mut() = Some([1, 2, 3, 4]);
+        frame.encode()
+    }
+
+    fn server(frames: &[Vec<u8>]) -> WebSocket<Mock> {
+        WebSocket::server(Mock {
+            input: Cursor::new(frames.concat()),
+            output: Vec::new(),
+        })
+    }
+
+    /// the frames the server sent
+    fn sent(socket: &WebSocket<Mock>) -> Vec<Frame> {
+        let mut buf = &socket.stream().output[..];
+        let mut frames = Vec::new();
+        while !buf.is_empty() {
+            let (frame, len) = Frame::decode(buf).unwrap();
+            assert_eq!(frame.mask(), None);
+            frames.push(frame);
+            buf = &buf[len..];
+        }
+        frames
+    }
+
+    /// the status code of the close frame the server sent last
+    fn close_code(socket: &WebSocket<Mock>) -> Option<u16> {
+        let frame = sent(socket).pop()?;
+        assert_eq!(frame.opcode(), OpCode::Close);
+        Some(u16::from_be_bytes([frame.payload()[0], frame.payload()[1]]))
+    }
+
+    #[test]
+    fn test_frame_lengths() {
+        for len in [0, 125, 126, 0xffff, 0x10000] {
+            let frame = Frame::new(true, OpCode::Binary, vec![7; len]);
+            let encoded = frame.encode();
+            assert_eq!(Frame::decode(&encoded), Ok((frame, encoded.len())));
+            assert_eq!(
+                Frame::decode(&encoded[..encoded.len() - 1]).map(|_| ()),
+                Err(Error::NotEnoughData)
+            );
+        }
+    }
+
+    #[test]
+    fn test_read_messages() {
+        let mut socket = server(&[
+            client_frame(true, OpCode::Text, "Hello".as_bytes()),
+            client_frame(true, OpCode::Binary, &[0, 1, 2]),
+            client_frame(true, OpCode::Close, &1000_u16.to_be_bytes()),
+        ]);
+
+        assert_eq!(socket.read().unwrap(), Message::Text("Hello".to_owned()));
+        assert_eq!(socket.read().unwrap(), Message::Binary(vec![0, 1, 2]));
+        assert_eq!(
+            socket.read().unwrap(),
+            Message::Close(Some(CloseFrame::new(1000, String::new())))
+        );
+        assert_eq!(close_code(&socket), Some(1000));
+
+        let err = socket.read().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotConnected);
+        assert!(socket.send(Message::Text("late".to_owned())).is_err());
+    }
+
+    #[test]
+    fn test_fragmentation_and_ping() {
+        // "κόσμε" with a character split between the fragments
+        let text = "κόσμε".as_bytes();
+        let mut socket = server(&[
+            client_frame(false, OpCode::Text, &text[..3]),
+            client_frame(true, OpCode::Ping, b"ping"),
+            client_frame(false, OpCode::Continuation, &text[3..7]),
+            client_frame(true, OpCode::Continuation, &text[7..]),
+        ]);
+
+        assert_eq!(socket.read().unwrap(), Message::Ping(b"ping".to_vec()));
+        assert_eq!(socket.read().unwrap(), Message::Text("κόσμε".to_owned()));
+        assert_eq!(
+            sent(&socket),
+            [Frame::new(true, OpCode::Pong, b"ping".to_vec())]
+        );
+    }
+
+    #[test]
+    fn test_protocol_errors() {
+        let mut unmasked = Frame::new(true, OpCode::Text, b"hi".to_vec()).encode();
+        let mut reserved_bits = client_frame(true, OpCode::Text, b"hi");
+        reserved_bits[0] |= 0x40;
+        let mut reserved_opcode = client_frame(true, OpCode::Text, b"hi");
+        reserved_opcode[0] = 0x83;
+
+        let cases = [
+            vec![std::mem::take(&mut unmasked)],
+            vec![reserved_bits],
+            vec![reserved_opcode],
+            vec![client_frame(true, OpCode::Ping, &[0; 126])],
+            vec![client_frame(false, OpCode::Ping, b"")],
+            vec![client_frame(true, OpCode::Continuation, b"hi")],
+            vec![
+                client_frame(false, OpCode::Text, b"h"),
+                client_frame(true, OpCode::Text, b"i"),
+            ],
+            vec![client_frame(true, OpCode::Close, &[3])],
+            vec![client_frame(true, OpCode::Close, &1005_u16.to_be_bytes())],
+            vec![client_frame(true, OpCode::Close, &2999_u16.to_be_bytes())],
+        ];
+
+        for frames in cases {
+            let mut socket = server(&frames);
+            let err = socket.read().unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+            assert_eq!(close_code(&socket), Some(CloseFrame::PROTOCOL_ERROR));
+        }
+    }
+
+    #[test]
+    fn test_invalid_utf8() {
+        let mut close = 1000_u16.to_be_bytes().to_vec();
+        close.push(0xff);
+
+        let cases = [
+            vec![client_frame(true, OpCode::Text, &[0xce, 0xba, 0xff])],
+            // fails on the second fragment already, the third one is never read
+            vec![
+                client_frame(false, OpCode::Text, &[0xce, 0xba, 0xe1]),
+                client_frame(false, OpCode::Continuation, &[0xff]),
+            ],
+            vec![client_frame(true, OpCode::Text, &[0xce])],
+            vec![client_frame(true, OpCode::Close, &close)],
+        ];
+
+        for frames in cases {
+            let mut socket = server(&frames);
+            let err = socket.read().unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+            assert_eq!(close_code(&socket), Some(CloseFrame::INVALID_PAYLOAD));
+        }
+    }
+
+    #[test]
+    fn test_message_too_big() {
+        let mut socket = server(&[
+            client_frame(false, OpCode::Binary, &[0; 6]),
+            client_frame(true, OpCode::Continuation, &[0; 6]),
+        ]);
+        *socket.max_message_len_mut() = 10;
+
+        let err = socket.read().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(close_code(&socket), Some(CloseFrame::MESSAGE_TOO_BIG));
+    }
+
+    #[test]
+    fn test_validate_response() {
+        let mut request = RequestBuilder::websocket("/chat".to_owned());
+        request.header_mut().insert(
+            "Sec-WebSocket-Protocol".to_owned(),
+            "chat, superchat".to_owned(),
+        );
+        let key = request.header()["Sec-WebSocket-Key"].clone();
+        assert!(is_valid_key(&key));
+        assert_ne!(
+            key,
+            RequestBuilder::websocket("/".to_owned()).header()["Sec-WebSocket-Key"]
+        );
+
+        let response = |extra: &str, accept: &str| {
+            format!(
+                "HTTP/1.1 101 Switching Protocols\r\nConnection: upgrade\r\n\
+                Upgrade: WebSocket\r\nSec-WebSocket-Accept: {accept}\r\n{extra}\r\n"
+            )
+        };
+        let accept = accept_key(&key);
+        let validate = |raw: String| {
+            validate_response(
+                &request,
+                &Response::from_bytes_head(raw.as_bytes()).unwrap(),
+            )
+        };
+
+        assert_eq!(validate(response("", &accept)), Ok(()));
+        assert_eq!(
+            validate(response("Sec-WebSocket-Protocol: superchat\r\n", &accept)),
+            Ok(())
+        );
+
+        for raw in [
+            response("", &accept_key("dGhlIHNhbXBsZSBub25jZQ==")),
+            response("Sec-WebSocket-Protocol: mqtt\r\n", &accept),
+            response("Sec-WebSocket-Extensions: permessage-deflate\r\n", &accept),
+            response("", &accept).replace("101 Switching Protocols", "200 OK"),
+            response("", &accept).replace("Connection: upgrade", "Connection: close"),
+        ] {
+            assert_eq!(validate(raw), Err(Error::InvalidHeader));
+        }
+    }
+
+    #[test]
+    fn test_connect() {
+        fn echo(request: Request) -> io::Result<ResponseBuilder> {
+            let Ok(response) = handshake(&request) else {
+                return Ok(ResponseBuilder::bad_request());
+            };
+
+            let upgrade = request.extensions().get::<crate::Upgrade>().unwrap();
+            upgrade.on_upgrade(|upgraded| {
+                let mut socket = WebSocket::server(upgraded);
+                while let Ok(message) = socket.read() {
+                    if let Message::Text(_) | Message::Binary(_) = message {
+                        socket.send(message).unwrap();
+                    }
+                }
+            });
+
+            Ok(response)
+        }
+
+        let server = crate::Server::bind("127.0.0.1:0", echo).unwrap();
+        let addr = server.local_addr().unwrap().to_string();
+        let handle = server.shutdown_handle().unwrap();
+        let server = std::thread::spawn(move || server.run());
+
+        let request = RequestBuilder::websocket("/".to_owned());
+        let (mut socket, response) = connect(&addr, request).unwrap();
+        assert_eq!(response.status(), Status::SwitchingProtocols);
+
+        let text = Message::Text("x".repeat(70_000));
+        socket.send(text.clone()).unwrap();
+        assert_eq!(socket.read().unwrap(), text);
+
+        socket.send(Message::Binary(vec![1, 2, 3])).unwrap();
+        assert_eq!(socket.read().unwrap(), Message::Binary(vec![1, 2, 3]));
+
+        socket.send(Message::Ping(b"ping".to_vec())).unwrap();
+        assert_eq!(socket.read().unwrap(), Message::Pong(b"ping".to_vec()));
+
+        socket
+            .close(Some(CloseFrame::new(CloseFrame::NORMAL, "bye".to_owned())))
+            .unwrap();
+        assert_eq!(
+            socket.read().unwrap(),
+            Message::Close(Some(CloseFrame::new(CloseFrame::NORMAL, String::new())))
+        );
+
+        let request = RequestBuilder::get("/".to_owned());
+        let err = connect(&addr, request).map(|_| ()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        handle.shutdown();
+        server.join().unwrap().unwrap();
+    }
+
+    #[cfg(feature = "deflate")]
+    #[test]
+    fn test_connect_deflate() {
+        fn echo(request: Request) -> io::Result<ResponseBuilder> {
+            let Ok((response, deflate)) = handshake_deflate(&request, Deflate::new()) else {
+                return Ok(ResponseBuilder::bad_request());
+            };
+
+            let upgrade = request.extensions().get::<crate::Upgrade>().unwrap();
+            upgrade.on_upgrade(move |upgraded| {
+                let mut socket = WebSocket::server(upgraded);
+                socket.set_deflate(deflate);
+                while let Ok(message) = socket.read() {
+                    if let Message::Text(_) | Message::Binary(_) = message {
+                        socket.send(message).unwrap();
+                    }
+                }
+            });
+
+            Ok(response)
+        }
+
+        let server = crate::Server::bind("127.0.0.1:0", echo).unwrap();
+        let addr = server.local_addr().unwrap().to_string();
+        let handle = server.shutdown_handle().unwrap();
+        let server = std::thread::spawn(move || server.run());
+
+        let mut config = Deflate::new();
+        *config.client_no_context_takeover_mut() = true;
+
+        let mut request = RequestBuilder::websocket("/".to_owned());
+        request
+            .header_mut()
+            .insert("Sec-WebSocket-Extensions".into(), config.offer());
+
+        let (mut socket, _) = connect(&addr, request).unwrap();
+        assert_eq!(socket.deflate(), Some(config));
+
+        // the second message is compressed with the window of the first one by the server
+        for _ in 0..2 {
+            let text = Message::Text("Hello ".repeat(10_000));
+            socket.send(text.clone()).unwrap();
+            assert_eq!(socket.read().unwrap(), text);
+        }
+
+        socket.send(Message::Binary(Vec::new())).unwrap();
+        assert_eq!(socket.read().unwrap(), Message::Binary(Vec::new()));
+
+        // only data messages are compressed
+        socket.send(Message::Ping(b"ping".to_vec())).unwrap();
+        assert_eq!(socket.read().unwrap(), Message::Pong(b"ping".to_vec()));
+
+        socket.close(None).unwrap();
+        assert_eq!(socket.read().unwrap(), Message::Close(None));
+
+        handle.shutdown();
+        server.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_handshake() {
+        let raw_request = b"GET /chat HTTP/1.1\r\nConnection: keep-alive, Upgrade\r\n\
+            Upgrade: websocket\r\nSec-WebSocket-Version: 13\r\nSec-WebSocket-Key: a2V5\r\n\r\n";
+        let request = Request::from_bytes(raw_request).unwrap();
+        assert_eq!(handshake(&request), Err(Error::InvalidHeader));
+
+        assert!(is_valid_key("dGhlIHNhbXBsZSBub25jZQ=="));
+        assert!(!is_valid_key("a2V5"));
+    }
+}
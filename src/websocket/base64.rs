@@ -0,0 +1,84 @@
+//! base64 with the standard alphabet and padding, see RFC 4648 section 4
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+pub(crate) fn encode(data: &[u8]) -> String {
+    let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let bits = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                let index = (bits >> (18 - 6 * i)) & 0x3f;
+                encoded.push(ALPHABET[index as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+
+    encoded
+}
+
+/// returns `None` if `encoded` is not valid padded base64
+pub(crate) fn decode(encoded: &str) -> Option<Vec<u8>> {
+    let encoded = encoded.as_bytes();
+
+    if !encoded.len().is_multiple_of(4) {
+        return None;
+    }
+
+    let mut decoded = Vec::with_capacity(encoded.len() / 4 * 3);
+
+    for (i, chunk) in encoded.chunks(4).enumerate() {
+        let last = i == encoded.len() / 4 - 1;
+        let padding = chunk.iter().rev().take_while(|b| **b == b'=').count();
+
+        if padding > 2 || (padding > 0 && !last) {
+            return None;
+        }
+
+        let mut bits = 0_u32;
+        for b in &chunk[..4 - padding] {
+            let value = ALPHABET.iter().position(|a| a == b)?;
+            bits = bits << 6 | value as u32;
+        }
+        bits <<= 6 * padding;
+
+        let bytes = bits.to_be_bytes();
+        decoded.extend_from_slice(&bytes[1..4 - padding]);
+    }
+
+    Some(decoded)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let cases: [(&[u8], &str); 4] = [
+            (b"", ""),
+            (b"f", "Zg=="),
+            (b"fo", "Zm8="),
+            (b"foobar", "Zm9vYmFy"),
+        ];
+
+        for (data, encoded) in cases {
+            assert_eq!(encode(data), encoded);
+            assert_eq!(decode(encoded).as_deref(), Some(data));
+        }
+
+        assert_eq!(decode("Zg="), None);
+        assert_eq!(decode("Z==="), None);
+        assert_eq!(decode("Zg==Zg=="), None);
+        assert_eq!(decode("Z!=="), None);
+    }
+}
@@ -0,0 +1,399 @@
+//! the permessage-deflate extension, see RFC 7692
+//!
+//! only available with the `deflate` feature enabled.
+
+use flate2::{Compress, Compression, Decompress, FlushCompress, FlushDecompress, Status};
+
+use crate::Error;
+
+/// the name of the extension in `Sec-WebSocket-Extensions`
+const NAME: &str = "permessage-deflate";
+
+/// appended to a compressed message by the sync flush, it is removed before sending and added
+/// again before decompressing, see RFC 7692 section 7.2.1
+const TRAILER: [u8; 4] = [0x00, 0x00, 0xff, 0xff];
+
+/// the parameters of the permessage-deflate extension
+///
+/// with context takeover (the default) the compressor of an endpoint keeps its state from one
+/// message to the next, which compresses similar messages much better but keeps a 32 KiB window
+/// per connection alive. The `*_no_context_takeover` parameters reset it after every message.
+///
+/// only the maximum window size of 15 bits is supported, offers asking the server for a smaller
+/// window are declined.
+///
+/// # Examples
+/// ```
+/// use reqse::{Request, websocket::{self, Deflate}};
+///
+/// let raw_request = b"GET /chat HTTP/1.1\r\nConnection: Upgrade\r\nUpgrade: websocket\r\n\
+///     Sec-WebSocket-Version: 13\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\
+///     Sec-WebSocket-Extensions: permessage-deflate; client_max_window_bits\r\n\r\n";
+/// let request = Request::from_bytes(raw_request).unwrap();
+///
+/// let mut config = Deflate::new();
+/// *config.server_no_context_takeover_mut() = true;
+///
+/// let (response, deflate) = websocket::handshake_deflate(&request, config).unwrap();
+/// assert_eq!(deflate, Some(config));
+/// assert_eq!(
+///     response.header().get("Sec-WebSocket-Extensions").unwrap(),
+///     "permessage-deflate; server_no_context_takeover"
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Deflate {
+    server_no_context_takeover: bool,
+    client_no_context_takeover: bool,
+}
+
+impl Deflate {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// whether the server resets its compressor after every message
+    pub fn server_no_context_takeover(&self) -> bool {
+        self.server_no_context_takeover
+    }
+
+    pub fn server_no_context_takeover_mut(&mut self) -> &mut bool {
+        &mut self.server_no_context_takeover
+    }
+
+    /// whether the client resets its compressor after every message
+    pub fn client_no_context_takeover(&self) -> bool {
+        self.client_no_context_takeover
+    }
+
+    pub fn client_no_context_takeover_mut(&mut self) -> &mut bool {
+        &mut self.client_no_context_takeover
+    }
+
+    /// the `Sec-WebSocket-Extensions` value offering the extension with these parameters
+    pub fn offer(&self) -> String {
+        self.header_value(None)
+    }
+
+    fn header_value(&self, server_max_window_bits: Option<u8>) -> String {
+        let mut value = NAME.to_owned();
+
+        if self.server_no_context_takeover {
+            value.push_str("; server_no_context_takeover");
+        }
+        if self.client_no_context_takeover {
+            value.push_str("; client_no_context_takeover");
+        }
+        if let Some(bits) = server_max_window_bits {
+            value.push_str(&format!("; server_max_window_bits={bits}"));
+        }
+
+        value
+    }
+
+    /// accepts the first acceptable offer in the `Sec-WebSocket-Extensions` value `offers` of
+    /// a client, `self` holds what the server demands in addition to the offer
+    ///
+    /// returns the negotiated parameters and the `Sec-WebSocket-Extensions` value of the
+    /// response
+    pub(crate) fn accept(&self, offers: &str) -> Option<(Self, String)> {
+        parse(offers)
+            .filter(|(name, _)| *name == NAME)
+            .find_map(|(_, params)| {
+                let mut deflate = *self;
+                let mut server_max_window_bits = None;
+
+                for (i, (key, value)) in params.iter().enumerate() {
+                    // every parameter may appear only once
+                    if params[..i].iter().any(|(k, _)| k == key) {
+                        return None;
+                    }
+
+                    match (*key, *value) {
+                        ("server_no_context_takeover", None) => {
+                            deflate.server_no_context_takeover = true;
+                        }
+                        ("client_no_context_takeover", None) => {
+                            deflate.client_no_context_takeover = true;
+                        }
+                        // the compressor always uses the largest window
+                        ("server_max_window_bits", Some(bits)) if window_bits(bits)? == 15 => {
+                            server_max_window_bits = Some(15);
+                        }
+                        // the decompressor accepts every window size
+                        ("client_max_window_bits", None) => (),
+                        ("client_max_window_bits", Some(bits)) => {
+                            window_bits(bits)?;
+                        }
+                        _ => return None,
+                    }
+                }
+
+                Some((deflate, deflate.header_value(server_max_window_bits)))
+            })
+    }
+
+    /// checks the `Sec-WebSocket-Extensions` value `accepted` of a server against the `offer`
+    /// of the client and returns the negotiated parameters
+    ///
+    /// # Error
+    /// - `Error::InvalidHeader` if the server accepted anything but permessage-deflate, did not
+    ///   offer it or demands a window smaller than 15 bits from the client
+    pub(crate) fn from_response(offer: Option<&str>, accepted: &str) -> Result<Self, Error> {
+        let offered = offer.is_some_and(|offer| parse(offer).any(|(name, _)| name == NAME));
+
+        let mut extensions = parse(accepted);
+        let (Some((NAME, params)), None, true) = (extensions.next(), extensions.next(), offered)
+        else {
+            return Err(Error::InvalidHeader);
+        };
+
+        let mut deflate = Self::new();
+
+        for (i, (key, value)) in params.iter().enumerate() {
+            if params[..i].iter().any(|(k, _)| k == key) {
+                return Err(Error::InvalidHeader);
+            }
+
+            match (*key, *value) {
+                ("server_no_context_takeover", None) => deflate.server_no_context_takeover = true,
+                ("client_no_context_takeover", None) => deflate.client_no_context_takeover = true,
+                ("server_max_window_bits", Some(bits)) if window_bits(bits).is_some() => (),
+                ("client_max_window_bits", Some(bits)) if window_bits(bits) == Some(15) => (),
+                _ => return Err(Error::InvalidHeader),
+            }
+        }
+
+        Ok(deflate)
+    }
+}
+
+/// parses a `Sec-WebSocket-Extensions` value into the names of the extensions and their
+/// parameters
+fn parse(value: &str) -> impl Iterator<Item = (&str, Vec<(&str, Option<&str>)>)> {
+    value.split(',').filter_map(|extension| {
+        let mut parts = extension.split(';').map(str::trim);
+        let name = parts.next().filter(|name| !name.is_empty())?;
+        let params = parts
+            .map(|param| match param.split_once('=') {
+                Some((key, value)) => (key.trim(), Some(value.trim().trim_matches('"'))),
+                None => (param, None),
+            })
+            .collect();
+        Some((name, params))
+    })
+}
+
+/// parses a window size, which has to be between 8 and 15 bits
+fn window_bits(bits: &str) -> Option<u8> {
+    bits.parse().ok().filter(|bits| (8..=15).contains(bits))
+}
+
+/// the compressor and decompressor of one end of a connection
+#[derive(Debug)]
+pub(crate) struct Context {
+    compress: Compress,
+    decompress: Decompress,
+    reset_compress: bool,
+    reset_decompress: bool,
+}
+
+impl Context {
+    /// `server` tells which end of the connection this is
+    pub(crate) fn new(deflate: Deflate, server: bool) -> Self {
+        let (own, peer) = if server {
+            (
+                deflate.server_no_context_takeover,
+                deflate.client_no_context_takeover,
+            )
+        } else {
+            (
+                deflate.client_no_context_takeover,
+                deflate.server_no_context_takeover,
+            )
+        };
+
+        Self {
+            compress: Compress::new(Compression::default(), false),
+            decompress: Decompress::new(false),
+            reset_compress: own,
+            reset_decompress: peer,
+        }
+    }
+
+    /// compresses the payload of a message
+    pub(crate) fn compress(&mut self, payload: &[u8]) -> Result<Vec<u8>, Error> {
+        let mut out = Vec::with_capacity(payload.len() / 2 + 64);
+        let start = self.compress.total_in();
+
+        loop {
+            let consumed = (self.compress.total_in() - start) as usize;
+            // the flush is complete once the compressor leaves output space unused
+            if consumed == payload.len() && out.len() < out.capacity() && !out.is_empty() {
+                break;
+            }
+            out.reserve(out.capacity().max(64));
+
+            self.compress
+                .compress_vec(&payload[consumed..], &mut out, FlushCompress::Sync)
+                .map_err(|_| Error::InvalidFrame)?;
+        }
+
+        if out.ends_with(&TRAILER) {
+            out.truncate(out.len() - TRAILER.len());
+        }
+
+        if self.reset_compress {
+            self.compress.reset();
+        }
+
+        Ok(out)
+    }
+
+    /// decompresses the payload of a message, which may be at most `max_len` bytes long
+    /// decompressed
+    ///
+    /// # Error
+    /// - `Error::InvalidFrame` if the payload is not valid deflate data
+    /// - `Error::TooLarge` if the decompressed payload is longer than `max_len`
+    pub(crate) fn decompress(&mut self, payload: &[u8], max_len: usize) -> Result<Vec<u8>, Error> {
+        let mut input = payload.to_vec();
+        input.extend_from_slice(&TRAILER);
+
+        let mut out = Vec::with_capacity(payload.len().saturating_mul(2).min(max_len) + 64);
+        let start = self.decompress.total_in();
+
+        loop {
+            let consumed = (self.decompress.total_in() - start) as usize;
+            let produced = out.len();
+
+            if out.len() == out.capacity() {
+                if out.len() > max_len {
+                    return Err(Error::TooLarge);
+                }
+                out.reserve(out.capacity());
+            }
+
+            let status = self
+                .decompress
+                .decompress_vec(&input[consumed..], &mut out, FlushDecompress::Sync)
+                .map_err(|_| Error::InvalidFrame)?;
+
+            let done = (self.decompress.total_in() - start) as usize == input.len();
+            let stalled =
+                self.decompress.total_in() - start == consumed as u64 && out.len() == produced;
+
+            if status == Status::StreamEnd || (done && out.len() < out.capacity()) {
+                break;
+            }
+            if stalled && out.len() < out.capacity() {
+                return Err(Error::InvalidFrame);
+            }
+        }
+
+        if out.len() > max_len {
+            return Err(Error::TooLarge);
+        }
+
+        if self.reset_decompress {
+            self.decompress.reset(false);
+        }
+
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_accept() {
+        let config = Deflate::new();
+
+        assert_eq!(
+            config.accept("permessage-deflate; client_max_window_bits"),
+            Some((config, "permessage-deflate".to_owned()))
+        );
+
+        // the first offer asks for a window the compressor does not support
+        let (deflate, value) = config
+            .accept(
+                "x-webkit-deflate-frame, permessage-deflate; server_max_window_bits=10, \
+                permessage-deflate; server_max_window_bits=\"15\"; client_no_context_takeover",
+            )
+            .unwrap();
+        assert!(deflate.client_no_context_takeover());
+        assert_eq!(
+            value,
+            "permessage-deflate; client_no_context_takeover; server_max_window_bits=15"
+        );
+
+        for offer in [
+            "permessage-deflate; server_no_context_takeover; server_no_context_takeover",
+            "permessage-deflate; client_max_window_bits=16",
+            "permessage-deflate; unknown",
+            "x-webkit-deflate-frame",
+        ] {
+            assert_eq!(config.accept(offer), None, "{offer}");
+        }
+    }
+
+    #[test]
+    fn test_from_response() {
+        let offer = Some("permessage-deflate; client_max_window_bits");
+
+        let deflate =
+            Deflate::from_response(offer, "permessage-deflate; server_no_context_takeover")
+                .unwrap();
+        assert!(deflate.server_no_context_takeover());
+
+        for accepted in [
+            "permessage-deflate; client_max_window_bits=10",
+            "permessage-deflate; unknown",
+            "permessage-deflate, permessage-deflate",
+            "x-webkit-deflate-frame",
+        ] {
+            assert_eq!(
+                Deflate::from_response(offer, accepted),
+                Err(Error::InvalidHeader),
+                "{accepted}"
+            );
+        }
+        assert_eq!(
+            Deflate::from_response(None, "permessage-deflate"),
+            Err(Error::InvalidHeader)
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut deflate = Deflate::new();
+        *deflate.server_no_context_takeover_mut() = true;
+
+        let mut server = Context::new(deflate, true);
+        let mut client = Context::new(deflate, false);
+
+        // "Hello" compressed without context takeover, see RFC 7692 section 7.2.3.1
+        let hello = server.compress(b"Hello").unwrap();
+        assert_eq!(hello, [0xf2, 0x48, 0xcd, 0xc9, 0xc9, 0x07, 0x00]);
+        assert_eq!(server.compress(b"Hello").unwrap(), hello);
+
+        let json = br#"{"temperature": 21.5, "humidity": 40}"#.repeat(100);
+        for _ in 0..2 {
+            let compressed = client.compress(&json).unwrap();
+            assert!(compressed.len() < json.len() / 10);
+            assert_eq!(server.decompress(&compressed, json.len()).unwrap(), json);
+        }
+
+        let compressed = client.compress(&json).unwrap();
+        assert_eq!(
+            server.decompress(&compressed, json.len() - 1),
+            Err(Error::TooLarge)
+        );
+        assert_eq!(
+            client.decompress(&[0xff, 0xff, 0xff], 100),
+            Err(Error::InvalidFrame)
+        );
+    }
+}
@@ -0,0 +1,259 @@
+use crate::Error;
+
+/// the type of a frame, see RFC 6455 section 5.2
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OpCode {
+    Continuation,
+    Text,
+    Binary,
+    Close,
+    Ping,
+    Pong,
+}
+
+impl OpCode {
+    /// returns `None` for reserved opcodes
+    pub fn from_u8(opcode: u8) -> Option<Self> {
+        match opcode {
+            0x0 => Some(Self::Continuation),
+            0x1 => Some(Self::Text),
+            0x2 => Some(Self::Binary),
+            0x8 => Some(Self::Close),
+            0x9 => Some(Self::Ping),
+            0xa => Some(Self::Pong),
+            _ => None,
+        }
+    }
+
+    pub fn to_u8(self) -> u8 {
+        match self {
+            Self::Continuation => 0x0,
+            Self::Text => 0x1,
+            Self::Binary => 0x2,
+            Self::Close => 0x8,
+            Self::Ping => 0x9,
+            Self::Pong => 0xa,
+        }
+    }
+
+    /// whether frames of this type control the connection, they may appear between the
+    /// fragments of a message but can not be fragmented themselves
+    pub fn is_control(self) -> bool {
+        matches!(self, Self::Close | Self::Ping | Self::Pong)
+    }
+}
+
+/// a single WebSocket frame
+///
+/// frames sent by a client are masked with a random key, frames sent by a server are not.
+/// `decode` unmasks the payload and keeps the key in `mask`, `encode` masks the payload with
+/// `mask` if set.
+///
+/// # Examples
+/// ```
+/// use reqse::websocket::{Frame, OpCode};
+///
+/// let mut frame = Frame::new(true, OpCode::Text, b"Hello".to_vec());
+/// *frame.mask_mut() = Some([0x37, 0xfa, 0x21, 0x3d]);
+///
+/// let encoded = frame.encode();
+/// assert_eq!(encoded, b"\x81\x85\x37\xfa\x21\x3d\x7f\x9f\x4d\x51\x58");
+///
+/// let (decoded, len) = Frame::decode(&encoded).unwrap();
+/// assert_eq!(decoded, frame);
+/// assert_eq!(decoded.payload(), b"Hello");
+/// assert_eq!(len, encoded.len());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    fin: bool,
+    rsv1: bool,
+    opcode: OpCode,
+    mask: Option<[u8; 4]>,
+    payload: Vec<u8>,
+}
+
+impl Frame {
+    pub fn new(fin: bool, opcode: OpCode, payload: Vec<u8>) -> Self {
+        Self {
+            fin,
+            rsv1: false,
+            opcode,
+            mask: None,
+            payload,
+        }
+    }
+
+    /// whether this is the last frame of a message
+    pub fn fin(&self) -> bool {
+        self.fin
+    }
+
+    pub fn fin_mut(&mut self) -> &mut bool {
+        &mut self.fin
+    }
+
+    /// the first reserved bit, which marks a compressed message when permessage-deflate is
+    /// negotiated
+    pub fn rsv1(&self) -> bool {
+        self.rsv1
+    }
+
+    pub fn rsv1_mut(&mut self) -> &mut bool {
+        &mut self.rsv1
+    }
+
+    pub fn opcode(&self) -> OpCode {
+        self.opcode
+    }
+
+    pub fn opcode_mut(&mut self) -> &mut OpCode {
+        &mut self.opcode
+    }
+
+    pub fn mask(&self) -> Option<[u8; 4]> {
+        self.mask
+    }
+
+    pub fn mask_mut(&mut self) -> &mut Option<[u8; 4]> {
+        &mut self.mask
+    }
+
+    /// the unmasked payload
+    pub fn payload(&self) -> &[u8] {
+        &self.payload
+    }
+
+    pub fn payload_mut(&mut self) -> &mut Vec<u8> {
+        &mut self.payload
+    }
+
+    pub fn into_payload(self) -> Vec<u8> {
+        self.payload
+    }
+
+    /// decodes the frame at the start of `buf` and returns it together with its length
+    ///
+    /// # Error
+    /// - `Error::NotEnoughData` if `buf` does not contain the whole frame
+    /// - `Error::InvalidFrame` if the second or third reserved bit or a reserved opcode is
+    ///   used, or a control frame is fragmented or longer than 125 bytes
+    pub fn decode(buf: &[u8]) -> Result<(Self, usize), Error> {
+        Self::decode_limited(buf, usize::MAX)
+    }
+
+    /// like `decode`, but fails with `Error::TooLarge` as soon as the header announces a
+    /// payload longer than `max_payload_len`
+    pub(crate) fn decode_limited(
+        buf: &[u8],
+        max_payload_len: usize,
+    ) -> Result<(Self, usize), Error> {
+        let [first, second, ..] = *buf else {
+            return Err(Error::NotEnoughData);
+        };
+
+        // no extension uses the second and third reserved bit
+        if first & 0x30 != 0 {
+            return Err(Error::InvalidFrame);
+        }
+
+        let fin = first & 0x80 != 0;
+        let rsv1 = first & 0x40 != 0;
+        let opcode = OpCode::from_u8(first & 0x0f).ok_or(Error::InvalidFrame)?;
+        let masked = second & 0x80 != 0;
+
+        let (payload_len, mut offset) = match second & 0x7f {
+            126 => (u16::from_be_bytes(read_array(buf, 2)?) as u64, 4),
+            127 => (u64::from_be_bytes(read_array(buf, 2)?), 10),
+            len => (len as u64, 2),
+        };
+
+        if opcode.is_control() && (!fin || payload_len > 125) {
+            return Err(Error::InvalidFrame);
+        }
+
+        // the most significant bit of a 64 bit length must be zero
+        if payload_len >> 63 != 0 {
+            return Err(Error::InvalidFrame);
+        }
+
+        let payload_len = usize::try_from(payload_len).map_err(|_| Error::TooLarge)?;
+        if payload_len > max_payload_len {
+            return Err(Error::TooLarge);
+        }
+
+        let mask = if masked {
+            let mask = read_array(buf, offset)?;
+            offset += 4;
+            Some(mask)
+        } else {
+            None
+        };
+
+        let end = offset.checked_add(payload_len).ok_or(Error::TooLarge)?;
+        let mut payload = buf.get(offset..end).ok_or(Error::NotEnoughData)?.to_vec();
+
+        if let Some(mask) = mask {
+            apply_mask(&mut payload, mask);
+        }
+
+        let frame = Self {
+            fin,
+            rsv1,
+            opcode,
+            mask,
+            payload,
+        };
+
+        Ok((frame, end))
+    }
+
+    /// encodes the frame, masking the payload if `mask` is set
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(14 + self.payload.len());
+
+        let fin = if self.fin { 0x80 } else { 0 };
+        let rsv1 = if self.rsv1 { 0x40 } else { 0 };
+        buf.push(fin | rsv1 | self.opcode.to_u8());
+
+        let mask_bit = if self.mask.is_some() { 0x80 } else { 0 };
+        match self.payload.len() {
+            len @ 0..126 => buf.push(mask_bit | len as u8),
+            len @ 126..=0xffff => {
+                buf.push(mask_bit | 126);
+                buf.extend_from_slice(&(len as u16).to_be_bytes());
+            }
+            len => {
+                buf.push(mask_bit | 127);
+                buf.extend_from_slice(&(len as u64).to_be_bytes());
+            }
+        }
+
+        if let Some(mask) = self.mask {
+            buf.extend_from_slice(&mask);
+        }
+
+        let payload_start = buf.len();
+
+        buf.extend_from_slice(&self.payload);
+
+        if let Some(mask) = self.mask {
+            apply_mask(&mut buf[payload_start..], mask);
+        }
+
+        buf
+    }
+}
+
+/// masks or unmasks `payload`, see RFC 6455 section 5.3
+fn apply_mask(payload: &mut [u8], mask: [u8; 4]) {
+    for (i, b) in payload.iter_mut().enumerate() {
+        *b ^= mask[i % 4];
+    }
+}
+
+fn read_array<const N: usize>(buf: &[u8], offset: usize) -> Result<[u8; N], Error> {
+    buf.get(offset..offset + N)
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or(Error::NotEnoughData)
+}
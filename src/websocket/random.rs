@@ -0,0 +1,32 @@
+//! random bytes for `Sec-WebSocket-Key` and masking keys
+//!
+//! the std `RandomState` seeds SipHash with random keys taken from the operating system, hashing
+//! a counter and the current time with it gives unpredictable values without a dependency. This
+//! is good enough for WebSocket keys and masks, which only need to be unpredictable for the
+//! peer and proxies in between, but not for cryptography.
+
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    sync::atomic::{AtomicU64, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+fn random_u64() -> u64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(COUNTER.fetch_add(1, Ordering::Relaxed));
+    if let Ok(time) = SystemTime::now().duration_since(UNIX_EPOCH) {
+        hasher.write_u128(time.as_nanos());
+    }
+    hasher.finish()
+}
+
+pub(crate) fn random_bytes<const N: usize>() -> [u8; N] {
+    let mut bytes = [0; N];
+    for chunk in bytes.chunks_mut(8) {
+        chunk.copy_from_slice(&random_u64().to_le_bytes()[..chunk.len()]);
+    }
+    bytes
+}